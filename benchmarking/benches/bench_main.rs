@@ -5,4 +5,6 @@ mod benchmarks;
 criterion_main! {
     benchmarks::single_thread,
     benchmarks::multi_thread,
+    benchmarks::async_multi_task,
+    benchmarks::scan_pollution,
 }
@@ -0,0 +1,70 @@
+use std::{convert::Infallible, future::ready, sync::Arc, time::Instant};
+
+use benchmarking::{trace::Op, workload::WorkloadSpec};
+use cache::AsyncCache;
+use criterion::{measurement::WallTime, BenchmarkGroup, BenchmarkId};
+use tokio::{runtime::Runtime, sync::Barrier};
+
+/// Like the multi-threaded benchmark, but with tasks on a Tokio runtime instead of threads.
+///
+/// A get is a `get_or_try_insert_with` whose loader returns the key right away, so every miss
+/// goes through the cache's loading path.
+pub fn benchmark_cache_async_multi_task(
+    id: BenchmarkId,
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    runtime: &Runtime,
+    task_count: usize,
+    cache: impl AsyncCache<String, String> + 'static,
+    workload: &WorkloadSpec,
+) {
+    let task_count = task_count as u64;
+    // Tasks can outlive the function that spawns them, so they need owned handles to the cache.
+    let cache = Arc::new(cache);
+
+    group.bench_function(id, |bencher| {
+        bencher.iter_custom(|iterations| {
+            runtime.block_on(async {
+                let task_count = task_count.min(iterations);
+                let barrier = Arc::new(Barrier::new(1 + task_count as usize));
+                let tasks: Vec<_> = (0..task_count)
+                    .map(|task_index| {
+                        let iterations_per_task = iterations / task_count;
+                        let task_barrier = barrier.clone();
+                        let cache = cache.clone();
+                        // Each task gets its own stream of the same workload.
+                        let workload = workload.clone().with_seed(workload.seed + task_index);
+                        let requests = workload.keyed_requests(100 * cache::MAX_SIZE);
+                        tokio::spawn(async move {
+                            task_barrier.wait().await;
+                            for (request, key) in
+                                requests.iter().cycle().take(iterations_per_task as usize)
+                            {
+                                match request.op {
+                                    Op::Get => {
+                                        let loaded = cache
+                                            .get_or_try_insert_with(
+                                                key.clone(),
+                                                ready(Ok::<_, Infallible>(key.clone())),
+                                            )
+                                            .await
+                                            .expect("the loader can't fail");
+                                        criterion::black_box(loaded);
+                                    }
+                                    Op::Set | Op::Delete => {
+                                        cache.set(key.clone(), key.clone()).await
+                                    }
+                                }
+                            }
+                        })
+                    })
+                    .collect();
+                barrier.wait().await;
+                let start = Instant::now();
+                for task in tasks {
+                    task.await.expect("a benchmark task panicked");
+                }
+                start.elapsed()
+            })
+        });
+    });
+}
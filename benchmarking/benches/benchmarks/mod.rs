@@ -1,46 +1,153 @@
-use cache::synchronized_cache;
+use async_multi_task_cache_test::benchmark_cache_async_multi_task;
+use benchmarking::{
+    hashlink_lru::HashlinkLruCache,
+    kcache::{KCache, SharableKCache},
+    lru_cache::LruCache,
+    lru_crate::LruCrateCache,
+    minimoka::MiniMokaCache,
+    quickcache::QuickCache,
+    workload::{KeyDistribution, WorkloadSpec},
+};
+use cache::{synchronized_cache, MAX_SIZE};
+use cache_policies::{LirsCache, TwoQCache};
 use criterion::{criterion_group, BenchmarkId, Criterion};
-use kcache::{KCache, SharableKCache};
-use lru_cache::LruCache;
+use example_sieve_cache::{ClockCache, SurvivorPolicy};
+use loading_cache::LoadingCache;
 use multi_thread_cache_test::benchmark_cache_multi_threaded;
 use pprof::criterion::{Output, PProfProfiler};
+use scan_pollution_test::benchmark_scan_pollution;
 use single_thread_cache_test::benchmark_cache_single_threaded;
 
-mod kcache;
-mod lru_cache;
+mod async_multi_task_cache_test;
 mod multi_thread_cache_test;
+mod scan_pollution_test;
 mod single_thread_cache_test;
 
+/// The workloads every implementation runs single-threaded, over a working set 2x the size of the
+/// caches with a 4:1 read:write ratio.
+fn workloads() -> [(&'static str, WorkloadSpec); 6] {
+    let key_space = 2 * MAX_SIZE as u64;
+    [
+        ("uniform", KeyDistribution::Uniform { key_space }),
+        (
+            "zipf",
+            KeyDistribution::Zipf {
+                key_space,
+                alpha: 1.0,
+            },
+        ),
+        (
+            "scan",
+            KeyDistribution::Scan {
+                key_space,
+                alpha: 1.0,
+                scan_length: key_space,
+                scan_every: 10 * key_space,
+            },
+        ),
+        (
+            "loop",
+            KeyDistribution::Loop {
+                loop_length: key_space,
+            },
+        ),
+        (
+            "one-hit-wonders",
+            KeyDistribution::OneHitWonders {
+                key_space,
+                alpha: 1.0,
+                one_hit_fraction: 0.5,
+            },
+        ),
+        (
+            "shifting-hotspot",
+            KeyDistribution::ShiftingHotspot {
+                key_space,
+                hot_set_size: MAX_SIZE as u64 / 2,
+                hot_fraction: 0.9,
+                shift_every: 10 * key_space,
+            },
+        ),
+    ]
+    .map(|(name, keys)| (name, WorkloadSpec::new(keys).with_read_ratio(0.8)))
+}
+
 fn single_threaded_comparison(c: &mut Criterion) {
     let mut single_thread_benchmark_group = c.benchmark_group("single_thread");
 
-    benchmark_cache_single_threaded(
-        BenchmarkId::from_parameter("workshop"),
-        &mut single_thread_benchmark_group,
-        sieve_cache::SieveCache::new(),
-    );
+    for (workload_name, workload) in workloads() {
+        benchmark_cache_single_threaded(
+            "workshop",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            sieve_cache::SieveCache::new(),
+            &workload,
+        );
 
-    benchmark_cache_single_threaded(
-        BenchmarkId::from_parameter("example"),
-        &mut single_thread_benchmark_group,
-        example_sieve_cache::SieveCache::new(),
-    );
+        benchmark_cache_single_threaded(
+            "example",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            example_sieve_cache::SieveCache::new(),
+            &workload,
+        );
 
-    benchmark_cache_single_threaded(
-        BenchmarkId::from_parameter("k-cache"),
-        &mut single_thread_benchmark_group,
-        KCache::new(),
-    );
+        benchmark_cache_single_threaded(
+            "k-cache",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            KCache::new(),
+            &workload,
+        );
 
-    benchmark_cache_single_threaded(
-        BenchmarkId::from_parameter("lru"),
-        &mut single_thread_benchmark_group,
-        LruCache::new(),
-    );
+        benchmark_cache_single_threaded(
+            "lru",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            LruCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_single_threaded(
+            "lru-crate",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            LruCrateCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_single_threaded(
+            "quick-cache",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            QuickCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_single_threaded(
+            "mini-moka",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            MiniMokaCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_single_threaded(
+            "hashlink",
+            workload_name,
+            &mut single_thread_benchmark_group,
+            HashlinkLruCache::new(),
+            &workload,
+        );
+    }
 }
 
 fn multi_threaded_comparison(c: &mut Criterion) {
     let mut multi_thread_benchmark_group = c.benchmark_group("multi_thread");
+    let workload = WorkloadSpec::new(KeyDistribution::Uniform {
+        key_space: 2 * MAX_SIZE as u64,
+    })
+    .with_read_ratio(0.8);
 
     for thread_count in [1, 2, 4, 8, 12, 16] {
         benchmark_cache_multi_threaded(
@@ -48,6 +155,7 @@ fn multi_threaded_comparison(c: &mut Criterion) {
             &mut multi_thread_benchmark_group,
             thread_count,
             synchronized_cache(sieve_cache::SieveCache::new()),
+            &workload,
         );
 
         benchmark_cache_multi_threaded(
@@ -55,6 +163,7 @@ fn multi_threaded_comparison(c: &mut Criterion) {
             &mut multi_thread_benchmark_group,
             thread_count,
             synchronized_cache(example_sieve_cache::SieveCache::new()),
+            &workload,
         );
 
         benchmark_cache_multi_threaded(
@@ -62,6 +171,7 @@ fn multi_threaded_comparison(c: &mut Criterion) {
             &mut multi_thread_benchmark_group,
             thread_count,
             SharableKCache::new(),
+            &workload,
         );
 
         benchmark_cache_multi_threaded(
@@ -69,11 +179,119 @@ fn multi_threaded_comparison(c: &mut Criterion) {
             &mut multi_thread_benchmark_group,
             thread_count,
             LruCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_multi_threaded(
+            BenchmarkId::new("lru-crate", thread_count),
+            &mut multi_thread_benchmark_group,
+            thread_count,
+            LruCrateCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_multi_threaded(
+            BenchmarkId::new("quick-cache", thread_count),
+            &mut multi_thread_benchmark_group,
+            thread_count,
+            QuickCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_multi_threaded(
+            BenchmarkId::new("mini-moka", thread_count),
+            &mut multi_thread_benchmark_group,
+            thread_count,
+            MiniMokaCache::new(),
+            &workload,
+        );
+
+        benchmark_cache_multi_threaded(
+            BenchmarkId::new("hashlink", thread_count),
+            &mut multi_thread_benchmark_group,
+            thread_count,
+            HashlinkLruCache::new(),
+            &workload,
         );
     }
 }
 
+fn async_multi_task_comparison(c: &mut Criterion) {
+    let mut async_multi_task_benchmark_group = c.benchmark_group("async_multi_task");
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .build()
+        .expect("the benchmark runtime should start");
+    let workload = WorkloadSpec::new(KeyDistribution::Uniform {
+        key_space: 2 * MAX_SIZE as u64,
+    })
+    .with_read_ratio(0.8);
+
+    for task_count in [1, 4, 16, 64, 256] {
+        benchmark_cache_async_multi_task(
+            BenchmarkId::new("workshop", task_count),
+            &mut async_multi_task_benchmark_group,
+            &runtime,
+            task_count,
+            LoadingCache::new(sieve_cache::SieveCache::new()),
+            &workload,
+        );
+
+        benchmark_cache_async_multi_task(
+            BenchmarkId::new("example", task_count),
+            &mut async_multi_task_benchmark_group,
+            &runtime,
+            task_count,
+            LoadingCache::new(example_sieve_cache::SieveCache::new()),
+            &workload,
+        );
+    }
+}
+
+fn scan_pollution_comparison(c: &mut Criterion) {
+    let mut scan_pollution_benchmark_group = c.benchmark_group("scan_pollution");
+
+    benchmark_scan_pollution(
+        "workshop",
+        &mut scan_pollution_benchmark_group,
+        sieve_cache::SieveCache::new(),
+    );
+
+    benchmark_scan_pollution(
+        "example",
+        &mut scan_pollution_benchmark_group,
+        example_sieve_cache::SieveCache::new(),
+    );
+
+    benchmark_scan_pollution(
+        "clock-2bit",
+        &mut scan_pollution_benchmark_group,
+        ClockCache::new(MAX_SIZE, 2, SurvivorPolicy::MoveToHead),
+    );
+
+    benchmark_scan_pollution(
+        "2q",
+        &mut scan_pollution_benchmark_group,
+        TwoQCache::new(MAX_SIZE),
+    );
+
+    benchmark_scan_pollution(
+        "lirs",
+        &mut scan_pollution_benchmark_group,
+        LirsCache::new(MAX_SIZE),
+    );
+
+    benchmark_scan_pollution(
+        "k-cache",
+        &mut scan_pollution_benchmark_group,
+        KCache::new(),
+    );
+
+    benchmark_scan_pollution("lru", &mut scan_pollution_benchmark_group, LruCache::new());
+}
+
 criterion_group!(single_thread, single_threaded_comparison);
+criterion_group!(scan_pollution, scan_pollution_comparison);
+criterion_group!(async_multi_task, async_multi_task_comparison);
 criterion_group! {
     name = multi_thread;
     config = Criterion::default().with_profiler(PProfProfiler::new(20000, Output::Flamegraph(None)));
@@ -3,15 +3,16 @@ use std::{
     time::Instant,
 };
 
+use benchmarking::{trace::Op, workload::WorkloadSpec};
 use cache::ShareableCache;
 use criterion::{measurement::WallTime, BenchmarkGroup, BenchmarkId};
-use rand::{Rng, SeedableRng};
 
 pub fn benchmark_cache_multi_threaded(
     id: BenchmarkId,
     group: &mut BenchmarkGroup<'_, WallTime>,
     thread_count: usize,
     cache: impl ShareableCache<String, String>,
+    workload: &WorkloadSpec,
 ) {
     let thread_count = thread_count as u64;
 
@@ -26,23 +27,23 @@ pub fn benchmark_cache_multi_threaded(
             std::thread::scope(|scope| {
                 let thread_count = thread_count.min(iterations);
                 let barrier = Arc::new(Barrier::new(1 + thread_count as usize));
-                for _ in 0..thread_count {
+                for thread_index in 0..thread_count {
                     let iterations_per_thread = iterations / thread_count;
                     let thread_barrier = barrier.clone();
                     let cache = cache.clone();
+                    // Each thread gets its own stream of the same workload.
+                    let workload = workload.clone().with_seed(workload.seed + thread_index);
                     scope.spawn(move || {
-                        let words: Vec<String> = (0..2 * cache::MAX_SIZE)
-                            .map(|i| format!("value {i}"))
-                            .collect();
+                        let requests = workload.keyed_requests(100 * cache::MAX_SIZE);
                         thread_barrier.wait();
-                        let mut random = rand::rngs::StdRng::from_entropy();
-                        for _ in 0..iterations_per_thread {
-                            let load = random.gen_ratio(4, 5);
-                            let key = random.gen_range(0..(2 * cache::MAX_SIZE));
-                            if load {
-                                criterion::black_box(cache.get(&words[key]));
-                            } else {
-                                cache.set(words[key].clone(), words[key].clone());
+                        for (request, key) in
+                            requests.iter().cycle().take(iterations_per_thread as usize)
+                        {
+                            match request.op {
+                                Op::Get => {
+                                    criterion::black_box(cache.get(key));
+                                }
+                                Op::Set | Op::Delete => cache.set(key.clone(), key.clone()),
                             }
                         }
                     });
@@ -0,0 +1,54 @@
+use benchmarking::workload::{KeyDistribution, WorkloadSpec};
+use cache::SizeLimitedCache;
+use criterion::{measurement::WallTime, BenchmarkGroup, BenchmarkId};
+
+/// How many keys are in the Zipf-distributed hot set. They fit comfortably in the cache.
+const HOT_SET_SIZE: usize = cache::MAX_SIZE / 2;
+/// How many distinct keys a full scan reads. A scan is much larger than the cache.
+const SCAN_SIZE: usize = 4 * cache::MAX_SIZE;
+/// How many hot set requests happen between scans.
+const REQUESTS_BETWEEN_SCANS: usize = 10 * cache::MAX_SIZE;
+
+/// Mixes Zipf-distributed reads of a hot set with periodic full scans of a large cold data set,
+/// like a service that also runs batch jobs.
+///
+/// Every request is a read-through: `get`, then `set` on a miss. Recency-based policies let each
+/// scan flush the hot set, while scan-resistant policies keep it. Throughput is what criterion
+/// reports; the hit ratio of the hot set requests is printed after each implementation runs.
+pub fn benchmark_scan_pollution(
+    name: &str,
+    group: &mut BenchmarkGroup<'_, WallTime>,
+    mut cache: impl SizeLimitedCache<String, String>,
+) {
+    let workload = WorkloadSpec::new(KeyDistribution::Scan {
+        key_space: HOT_SET_SIZE as u64,
+        alpha: 1.0,
+        scan_length: SCAN_SIZE as u64,
+        scan_every: REQUESTS_BETWEEN_SCANS as u64,
+    });
+    let requests = workload.keyed_requests(10 * (REQUESTS_BETWEEN_SCANS + SCAN_SIZE));
+
+    let mut hot_requests = 0_u64;
+    let mut hot_hits = 0_u64;
+    let mut next_request = requests.iter().cycle();
+
+    group.bench_function(BenchmarkId::from_parameter(name), |bencher| {
+        bencher.iter(|| {
+            let (request, key) = next_request.next().expect("cycle is infinite");
+            let hit = cache.get(key).is_some();
+            if !hit {
+                cache.set(key.clone(), key.clone());
+            }
+            // Scans read the keys above the hot set's key space.
+            if request.key < HOT_SET_SIZE as u64 {
+                hot_requests += 1;
+                hot_hits += u64::from(hit);
+            }
+        });
+    });
+
+    eprintln!(
+        "scan_pollution/{name}: hot set hit ratio {:.3}",
+        hot_hits as f64 / hot_requests.max(1) as f64
+    );
+}
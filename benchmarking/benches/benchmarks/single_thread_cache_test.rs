@@ -1,24 +1,40 @@
+use benchmarking::{trace::Op, workload::WorkloadSpec};
 use cache::SizeLimitedCache;
 use criterion::{measurement::WallTime, BenchmarkGroup, BenchmarkId};
-use rand::{Rng, SeedableRng};
 
+/// Replays a synthetic workload through a cache. Gets are read-throughs, `get` then `set` on a
+/// miss, and sets overwrite. The hit ratio of the gets is printed after each implementation runs.
 pub fn benchmark_cache_single_threaded(
-    id: BenchmarkId,
+    name: &str,
+    workload_name: &str,
     group: &mut BenchmarkGroup<'_, WallTime>,
     mut cache: impl SizeLimitedCache<String, String>,
+    workload: &WorkloadSpec,
 ) {
-    let words: Vec<String> = (0..2 * cache::MAX_SIZE)
-        .map(|i| format!("value {i}"))
-        .collect();
-    let mut random = rand::rngs::StdRng::seed_from_u64(37);
+    let requests = workload.keyed_requests(100 * cache::MAX_SIZE);
+    let mut next_request = requests.iter().cycle();
+    let mut gets = 0_u64;
+    let mut hits = 0_u64;
 
-    group.bench_function(id, |bencher| {
+    group.bench_function(BenchmarkId::new(name, workload_name), |bencher| {
         bencher.iter(|| {
-            let store = random.gen_range(0..(2 * cache::MAX_SIZE)) as usize;
-            let load = random.gen_range(0..(2 * cache::MAX_SIZE)) as usize;
-
-            cache.set(words[store].clone(), words[store].clone());
-            criterion::black_box(cache.get(&words[load]));
+            let (request, key) = next_request.next().expect("cycle is infinite");
+            match request.op {
+                Op::Get => {
+                    let hit = criterion::black_box(cache.get(key)).is_some();
+                    if !hit {
+                        cache.set(key.clone(), key.clone());
+                    }
+                    gets += 1;
+                    hits += u64::from(hit);
+                }
+                Op::Set | Op::Delete => cache.set(key.clone(), key.clone()),
+            }
         });
     });
+
+    eprintln!(
+        "single_thread/{name}/{workload_name}: hit ratio {:.3}",
+        hits as f64 / gets.max(1) as f64
+    );
 }
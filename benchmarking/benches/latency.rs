@@ -0,0 +1,147 @@
+//! Records per-operation latency percentiles for gets and sets, for each implementation at each
+//! thread count, with the same workload as the `multi_thread` criterion benchmark.
+//!
+//! `cargo bench --bench latency` prints a CSV summary and also writes it, along with the full
+//! histograms as an HdrHistogram interval log, to `target/latency/`. Pass a filter like
+//! `cargo bench --bench latency -- latency/k-cache` to run only some implementations.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use benchmarking::{
+    hashlink_lru::HashlinkLruCache,
+    kcache::SharableKCache,
+    latency::{record_latencies, write_interval_log, LatencySummary, OperationLatencies},
+    lru_cache::LruCache,
+    lru_crate::LruCrateCache,
+    minimoka::MiniMokaCache,
+    quickcache::QuickCache,
+    workload::{KeyDistribution, WorkloadSpec},
+};
+use cache::{synchronized_cache, ShareableCache, MAX_SIZE};
+
+const THREAD_COUNTS: [usize; 6] = [1, 2, 4, 8, 12, 16];
+const OPERATIONS_PER_THREAD: usize = 200_000;
+
+struct Results {
+    filter: Option<String>,
+    workload: WorkloadSpec,
+    runs: Vec<(String, usize, OperationLatencies)>,
+}
+
+impl Results {
+    fn measure<Cache: ShareableCache<String, String>>(
+        &mut self,
+        implementation: &str,
+        new_cache: impl Fn() -> Cache,
+    ) {
+        let id = format!("latency/{implementation}");
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !id.contains(filter))
+        {
+            return;
+        }
+        for thread_count in THREAD_COUNTS {
+            eprintln!("{id}/{thread_count}");
+            let latencies = record_latencies(
+                &new_cache(),
+                thread_count,
+                OPERATIONS_PER_THREAD,
+                &self.workload,
+            );
+            self.runs
+                .push((implementation.to_string(), thread_count, latencies));
+        }
+    }
+
+    fn summaries(&self) -> Vec<LatencySummary> {
+        self.runs
+            .iter()
+            .flat_map(|(implementation, threads, latencies)| {
+                [
+                    LatencySummary::new(implementation, *threads, "get", &latencies.get),
+                    LatencySummary::new(implementation, *threads, "set", &latencies.set),
+                ]
+            })
+            .collect()
+    }
+
+    fn write(&self, directory: &Path) -> io::Result<()> {
+        fs::create_dir_all(directory)?;
+
+        let mut csv = BufWriter::new(File::create(directory.join("multi_thread.csv"))?);
+        writeln!(csv, "{}", LatencySummary::CSV_HEADER)?;
+        for summary in self.summaries() {
+            writeln!(csv, "{}", summary.to_csv_row())?;
+        }
+        csv.flush()?;
+
+        let histograms: Vec<_> = self
+            .runs
+            .iter()
+            .flat_map(|(implementation, threads, latencies)| {
+                [
+                    (
+                        format!("{implementation}/{threads}/get"),
+                        &latencies.get,
+                        latencies.elapsed,
+                    ),
+                    (
+                        format!("{implementation}/{threads}/set"),
+                        &latencies.set,
+                        latencies.elapsed,
+                    ),
+                ]
+            })
+            .collect();
+        let mut log = BufWriter::new(File::create(directory.join("multi_thread.hlog"))?);
+        write_interval_log(&mut log, &histograms)?;
+        log.flush()
+    }
+}
+
+fn main() -> io::Result<()> {
+    // cargo passes `--bench`; anything else that isn't a flag is a filter.
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with('-'));
+    let mut results = Results {
+        filter,
+        workload: WorkloadSpec::new(KeyDistribution::Uniform {
+            key_space: 2 * MAX_SIZE as u64,
+        })
+        .with_read_ratio(0.8),
+        runs: Vec::new(),
+    };
+
+    results.measure("workshop", || {
+        synchronized_cache(sieve_cache::SieveCache::new())
+    });
+    results.measure("example", || {
+        synchronized_cache(example_sieve_cache::SieveCache::new())
+    });
+    results.measure("k-cache", SharableKCache::new);
+    results.measure("lru", LruCache::new);
+    results.measure("lru-crate", LruCrateCache::new);
+    results.measure("quick-cache", QuickCache::new);
+    results.measure("mini-moka", MiniMokaCache::new);
+    results.measure("hashlink", HashlinkLruCache::new);
+
+    if results.runs.is_empty() {
+        return Ok(());
+    }
+    println!("{}", LatencySummary::CSV_HEADER);
+    for summary in results.summaries() {
+        println!("{}", summary.to_csv_row());
+    }
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .parent()
+        .expect("the target tmp directory is inside the target directory")
+        .join("latency");
+    results.write(&directory)?;
+    eprintln!("wrote {directory:?}");
+    Ok(())
+}
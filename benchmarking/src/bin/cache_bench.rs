@@ -0,0 +1,325 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+use benchmarking::{
+    history::{compare, git_revision, BenchmarkRun, Comparison, Significance, Verdict},
+    load::{run_load, LoadMode, LoadReport, LoadSpec},
+    policy::{Policy, SharedCacheVisitor},
+    workload::{WorkloadArgs, WorkloadSpec},
+};
+use cache::ShareableCache;
+use clap::{Parser, Subcommand, ValueEnum};
+
+/// Runs live load against shared caches from many threads for a fixed time, and reports
+/// throughput, hit ratio and latency percentiles.
+#[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Arguments {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArguments,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Compares two saved runs, and exits with an error if throughput or hit ratio changed
+    /// significantly between them.
+    Compare(CompareArguments),
+}
+
+#[derive(Debug, clap::Args)]
+struct RunArguments {
+    /// Comma-separated policies to run. Defaults to every policy except belady, which needs the
+    /// whole trace up front. Caches that aren't shareable already are wrapped in a mutex.
+    #[arg(long, value_delimiter = ',')]
+    policies: Vec<Policy>,
+
+    /// How many entries each cache holds.
+    #[arg(long, default_value_t = 10_000)]
+    capacity: usize,
+
+    /// Comma-separated thread counts to run each policy with.
+    #[arg(long, value_delimiter = ',', default_value = "1,2,4,8")]
+    threads: Vec<usize>,
+
+    /// Seconds to measure each run for.
+    #[arg(long, default_value_t = 5.0)]
+    duration: f64,
+
+    /// Seconds to run before measuring, to fill the cache.
+    #[arg(long, default_value_t = 1.0)]
+    warmup: f64,
+
+    /// Run open-loop at this many operations per second in total, instead of closed-loop.
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// Don't set the value when a get misses.
+    #[arg(long)]
+    no_read_through: bool,
+
+    /// How many times to run each policy and thread count. Comparing saved runs needs at least
+    /// two repetitions to tell a change from noise.
+    #[arg(long, default_value_t = 3)]
+    repeat: usize,
+
+    /// Save the results in the history directory, named after the git revision.
+    #[arg(long)]
+    save: bool,
+
+    /// The revision to save the results as, instead of asking git.
+    #[arg(long)]
+    revision: Option<String>,
+
+    #[arg(long, default_value = DEFAULT_HISTORY)]
+    history: PathBuf,
+
+    /// The synthetic workload. Its requests are split between the threads, and each thread
+    /// cycles through its share.
+    #[command(flatten)]
+    workload: WorkloadArgs,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, clap::Args)]
+struct CompareArguments {
+    /// The run to compare against: a revision saved in the history directory, or a path to a
+    /// saved run.
+    base: String,
+
+    /// The run to check, likewise.
+    new: String,
+
+    #[arg(long, default_value = DEFAULT_HISTORY)]
+    history: PathBuf,
+
+    /// The largest p-value that counts as a real change.
+    #[arg(long, default_value_t = 0.01)]
+    alpha: f64,
+
+    /// The smallest relative change that counts, however sure the test is.
+    #[arg(long, default_value_t = 0.02)]
+    min_change: f64,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+}
+
+const DEFAULT_HISTORY: &str = "target/bench-history";
+
+fn main() -> io::Result<ExitCode> {
+    env_logger::init();
+    let args = Arguments::parse();
+    match args.command {
+        None => run(args.run).map(|()| ExitCode::SUCCESS),
+        Some(Command::Compare(args)) => compare_runs(args),
+    }
+}
+
+fn run(args: RunArguments) -> io::Result<()> {
+    let invalid = |message: &str| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    if args.capacity == 0 {
+        return invalid("capacity must be at least 1");
+    }
+    if args.repeat == 0 {
+        return invalid("repeat must be at least 1");
+    }
+    if args.threads.contains(&0) {
+        return invalid("thread counts must be at least 1");
+    }
+    if !(args.duration > 0.0 && args.warmup >= 0.0) {
+        return invalid("duration must be positive and warmup can't be negative");
+    }
+    if args.rate.is_some_and(|rate| rate <= 0.0) {
+        return invalid("rate must be positive");
+    }
+    if args.policies.contains(&Policy::Belady) {
+        return invalid("belady needs the whole trace up front, so it can't serve live load");
+    }
+    let policies = if args.policies.is_empty() {
+        Policy::ALL
+            .into_iter()
+            .filter(|&policy| policy != Policy::Belady)
+            .collect()
+    } else {
+        args.policies
+    };
+    let mode = match args.rate {
+        None => LoadMode::Closed,
+        Some(operations_per_second) => LoadMode::Open {
+            operations_per_second,
+        },
+    };
+    let workload = args.workload.spec()?;
+    // Find out the revision first, so a run that can't be saved fails before it starts.
+    let revision = match (args.save, args.revision) {
+        (false, _) => None,
+        (true, Some(revision)) => Some(revision),
+        (true, None) => Some(git_revision()?),
+    };
+    if let Some(revision) = &revision {
+        BenchmarkRun::path(&args.history, revision)?;
+    }
+
+    let mut reports = Vec::new();
+    for &policy in &policies {
+        for &threads in &args.threads {
+            let spec = LoadSpec {
+                threads,
+                warmup: Duration::from_secs_f64(args.warmup),
+                duration: Duration::from_secs_f64(args.duration),
+                mode,
+                read_through: !args.no_read_through,
+                requests_per_thread: (args.workload.requests / threads).max(1),
+            };
+            for repetition in 0..args.repeat {
+                log::info!(
+                    "running {} with {threads} threads, repetition {}",
+                    policy.name(),
+                    repetition + 1
+                );
+                let report = policy
+                    .build_shared(
+                        args.capacity,
+                        Run {
+                            policy,
+                            spec: &spec,
+                            workload: &workload,
+                        },
+                    )
+                    .expect("belady was rejected above");
+                if let OutputFormat::Csv = args.format {
+                    if reports.is_empty() {
+                        println!("{}", LoadReport::CSV_HEADER);
+                    }
+                    println!("{}", report.to_csv_row());
+                }
+                reports.push(report);
+            }
+        }
+    }
+
+    if let OutputFormat::Json = args.format {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&reports).map_err(io::Error::other)?
+        );
+    }
+    if let Some(revision) = revision {
+        let arguments = std::env::args().skip(1).collect();
+        let path = BenchmarkRun::new(revision, arguments, reports).save(&args.history)?;
+        log::info!("saved the results to {}", path.display());
+    }
+    Ok(())
+}
+
+fn compare_runs(args: CompareArguments) -> io::Result<ExitCode> {
+    let load = |run: &str| {
+        let path = Path::new(run);
+        if path.is_file() {
+            BenchmarkRun::load(path)
+        } else {
+            BenchmarkRun::load(&BenchmarkRun::path(&args.history, run)?)
+        }
+    };
+    let (base, new) = (load(&args.base)?, load(&args.new)?);
+    if base.arguments != new.arguments {
+        log::warn!(
+            "the runs used different arguments, so they may not be comparable: {:?} and {:?}",
+            base.arguments,
+            new.arguments
+        );
+    }
+
+    let comparisons = compare(
+        &base,
+        &new,
+        Significance {
+            alpha: args.alpha,
+            min_change: args.min_change,
+        },
+    );
+    match args.format {
+        OutputFormat::Csv => {
+            println!("{}", Comparison::CSV_HEADER);
+            for comparison in &comparisons {
+                println!("{}", comparison.to_csv_row());
+            }
+        }
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&comparisons).map_err(io::Error::other)?
+            );
+        }
+    }
+
+    let significant = comparisons
+        .iter()
+        .filter(|comparison| comparison.is_significant())
+        .count();
+    // A gate that can't tell whether anything changed mustn't pass.
+    let undecided = comparisons
+        .iter()
+        .filter(|comparison| comparison.verdict == Verdict::TooFewSamples)
+        .count();
+    let missing = comparisons
+        .iter()
+        .filter(|comparison| comparison.verdict == Verdict::Missing)
+        .count();
+    if significant == 0 && undecided == 0 && missing == 0 {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if significant != 0 {
+        log::error!(
+            "{significant} significant changes from {} to {}",
+            base.revision,
+            new.revision
+        );
+    }
+    if undecided != 0 {
+        log::error!(
+            "{undecided} comparisons from {} to {} have too few samples to tell; save runs with \
+             --repeat 2 or more",
+            base.revision,
+            new.revision
+        );
+    }
+    if missing != 0 {
+        log::error!(
+            "{missing} comparisons measured in {} are missing from {}",
+            base.revision,
+            new.revision
+        );
+    }
+    Ok(ExitCode::FAILURE)
+}
+
+struct Run<'a> {
+    policy: Policy,
+    spec: &'a LoadSpec,
+    workload: &'a WorkloadSpec,
+}
+
+impl SharedCacheVisitor<String, String> for Run<'_> {
+    type Output = LoadReport;
+
+    fn visit(self, cache: impl ShareableCache<String, String>) -> LoadReport {
+        let result = run_load(&cache, self.spec, self.workload);
+        LoadReport::new(self.policy.name(), self.spec, &result)
+    }
+}
@@ -0,0 +1,81 @@
+use std::io;
+
+use benchmarking::{
+    memory::{measure_memory, CountingAllocator, MemoryReport},
+    policy::Policy,
+};
+use clap::{Parser, ValueEnum};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Fills each cache implementation to capacity with fixed-size `String` keys and values, and
+/// reports heap bytes per entry and allocations per operation.
+#[derive(Debug, Parser)]
+struct Arguments {
+    /// How many entries each cache holds.
+    #[arg(long, default_value_t = 10_000)]
+    capacity: usize,
+
+    /// The size of each key in bytes.
+    #[arg(long, default_value_t = 16)]
+    key_size: usize,
+
+    /// The size of each value in bytes.
+    #[arg(long, default_value_t = 64)]
+    value_size: usize,
+
+    /// Comma-separated policies to measure. Defaults to all of them.
+    #[arg(long, value_delimiter = ',')]
+    policies: Vec<Policy>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+}
+
+fn main() -> io::Result<()> {
+    env_logger::init();
+    let args = Arguments::parse();
+
+    if args.capacity == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "capacity must be at least 1",
+        ));
+    }
+    let policies = if args.policies.is_empty() {
+        Policy::ALL.to_vec()
+    } else {
+        args.policies
+    };
+
+    let reports: Vec<MemoryReport> = policies
+        .iter()
+        .map(|&policy| {
+            log::info!("measuring {}", policy.name());
+            measure_memory(policy, args.capacity, args.key_size, args.value_size)
+        })
+        .collect();
+
+    match args.format {
+        OutputFormat::Csv => {
+            println!("{}", MemoryReport::CSV_HEADER);
+            for report in &reports {
+                println!("{}", report.to_csv_row());
+            }
+        }
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&reports).map_err(io::Error::other)?
+            );
+        }
+    }
+    Ok(())
+}
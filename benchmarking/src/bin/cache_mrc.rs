@@ -0,0 +1,114 @@
+use std::{io, path::PathBuf};
+
+use benchmarking::{
+    mrc::{lru_mrc, MrcPoint, SampledSimulator, Sampling},
+    plot::miss_ratio_chart,
+    policy::Policy,
+    source::RequestSource,
+};
+use clap::{Parser, ValueEnum};
+
+/// Computes miss ratio curves: LRU's from stack distances in a single pass, and any other
+/// policy's by simulating a SHARDS-sampled trace at scaled-down capacities.
+#[derive(Debug, Parser)]
+struct Arguments {
+    #[command(flatten)]
+    source: RequestSource,
+
+    /// Comma-separated cache capacities, in items, to report miss ratios at.
+    #[arg(long, value_delimiter = ',', required = true)]
+    capacities: Vec<usize>,
+
+    /// Comma-separated policies to estimate curves for by sampled simulation. The stack distance
+    /// LRU curve is always reported.
+    #[arg(long, value_delimiter = ',')]
+    policies: Vec<Policy>,
+
+    /// Sample this share of keys, between 0 and 1.
+    #[arg(long, conflicts_with = "sample_size")]
+    sampling_rate: Option<f64>,
+
+    /// Sample at most this many distinct keys, lowering the rate as needed.
+    #[arg(long)]
+    sample_size: Option<usize>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+
+    /// Also write an SVG chart of miss ratio against capacity for each policy to this file.
+    #[arg(long)]
+    plot: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+}
+
+fn main() -> io::Result<()> {
+    env_logger::init();
+    let args = Arguments::parse();
+
+    if args.capacities.contains(&0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "capacities must be at least 1",
+        ));
+    }
+    let sampling = match (args.sampling_rate, args.sample_size) {
+        (Some(rate), _) if !(0.0 < rate && rate <= 1.0) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the sampling rate must be greater than 0 and at most 1",
+            ))
+        }
+        (Some(rate), _) => Sampling::FixedRate(rate),
+        (None, Some(0)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the sample size must be at least 1",
+            ))
+        }
+        (None, Some(size)) => Sampling::FixedSize(size),
+        (None, None) => Sampling::Exact,
+    };
+
+    // Each curve reads the requests as it goes, rather than holding the whole trace.
+    log::info!("computing the stack distance LRU curve");
+    let mut points = lru_mrc(args.source.stream()?, sampling, &args.capacities)?;
+    if !args.policies.is_empty() {
+        let simulator = SampledSimulator::new(args.source.stream()?, sampling)?;
+        log::info!("sampled {:.4} of keys", simulator.rate());
+        for &policy in &args.policies {
+            log::info!("estimating the {} curve", policy.name());
+            points.extend(simulator.mrc(policy, &args.capacities));
+        }
+    }
+
+    if let Some(plot) = &args.plot {
+        miss_ratio_chart(
+            "Miss ratio by capacity",
+            points
+                .iter()
+                .map(|point| (point.policy, point.capacity, point.miss_ratio)),
+        )
+        .write_svg(plot)?;
+    }
+
+    match args.format {
+        OutputFormat::Csv => {
+            println!("{}", MrcPoint::CSV_HEADER);
+            for point in &points {
+                println!("{}", point.to_csv_row());
+            }
+        }
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&points).map_err(io::Error::other)?
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::{io, path::PathBuf};
+
+use benchmarking::plot::throughput_chart;
+use clap::Parser;
+
+/// Draws an SVG chart of throughput against thread count from criterion's saved results for the
+/// multi-threaded benchmark. Run `cargo bench --bench bench_main -- multi_thread` first.
+#[derive(Debug, Parser)]
+struct Arguments {
+    /// The criterion results for the benchmark group to plot.
+    #[arg(long, default_value = "target/criterion/multi_thread")]
+    results: PathBuf,
+
+    /// Where to write the SVG.
+    #[arg(long, default_value = "multi_thread_throughput.svg")]
+    output: PathBuf,
+}
+
+fn main() -> io::Result<()> {
+    env_logger::init();
+    let args = Arguments::parse();
+
+    throughput_chart(&args.results)?.write_svg(&args.output)?;
+    log::info!("wrote {:?}", args.output);
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::{io, path::PathBuf};
+
+use benchmarking::{
+    plot::miss_ratio_chart,
+    policy::Policy,
+    simulator::{SimulationResult, Simulator},
+    source::RequestSource,
+};
+use clap::{Parser, ValueEnum};
+
+/// Replays a request trace through cache implementations at a list of capacities, and reports
+/// hit ratio, byte hit ratio and how many insertions didn't stay resident for each.
+#[derive(Debug, Parser)]
+struct Arguments {
+    #[command(flatten)]
+    source: RequestSource,
+
+    /// Comma-separated cache capacities, in items.
+    #[arg(long, value_delimiter = ',', required = true)]
+    capacities: Vec<usize>,
+
+    /// Comma-separated policies to simulate. Defaults to all of them.
+    #[arg(long, value_delimiter = ',')]
+    policies: Vec<Policy>,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Csv)]
+    format: OutputFormat,
+
+    /// Also write an SVG chart of miss ratio against capacity for each policy to this file.
+    #[arg(long)]
+    plot: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Csv,
+    Json,
+}
+
+fn main() -> io::Result<()> {
+    env_logger::init();
+    let args = Arguments::parse();
+
+    if args.capacities.contains(&0) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "capacities must be at least 1",
+        ));
+    }
+    let policies = if args.policies.is_empty() {
+        Policy::ALL.to_vec()
+    } else {
+        args.policies
+    };
+
+    let requests = args.source.load()?;
+    let simulator = Simulator::new(requests);
+
+    let mut results = Vec::new();
+    for &policy in &policies {
+        for &capacity in &args.capacities {
+            log::info!("simulating {} at capacity {capacity}", policy.name());
+            results.push(simulator.run(policy, capacity));
+        }
+    }
+
+    if let Some(plot) = &args.plot {
+        miss_ratio_chart(
+            "Miss ratio by capacity",
+            results
+                .iter()
+                .map(|result| (result.policy, result.capacity, 1.0 - result.hit_ratio)),
+        )
+        .write_svg(plot)?;
+    }
+
+    match args.format {
+        OutputFormat::Csv => {
+            println!("{}", SimulationResult::CSV_HEADER);
+            for result in &results {
+                println!("{}", result.to_csv_row());
+            }
+        }
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&results).map_err(io::Error::other)?
+            );
+        }
+    }
+    Ok(())
+}
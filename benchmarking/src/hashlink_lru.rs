@@ -0,0 +1,87 @@
+use std::sync::Mutex;
+
+use cache::{RemovableCache, ShareableCache, SizeLimitedCache, MAX_SIZE};
+
+/// An LRU cache built on the `hashlink` crate's linked hash map. Like [crate::lru_crate], sharing
+/// it takes a mutex.
+pub struct HashlinkLruCache<Key, Value>(Mutex<hashlink::LruCache<Key, Value>>);
+
+impl<Key, Value> HashlinkLruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(Mutex::new(hashlink::LruCache::new(capacity)))
+    }
+}
+
+impl<Key, Value> Default for HashlinkLruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for HashlinkLruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0
+            .get_mut()
+            .expect("mutex should work")
+            .get(key)
+            .cloned()
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0
+            .get_mut()
+            .expect("mutex should work")
+            .insert(key, value);
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for HashlinkLruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        self.0.get_mut().expect("mutex should work").remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.0.get_mut().expect("mutex should work").clear();
+    }
+}
+
+impl<Key, Value> ShareableCache<Key, Value> for HashlinkLruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Send,
+    Value: Clone + Send,
+{
+    fn get(&self, key: &Key) -> Option<Value> {
+        self.0.lock().expect("mutex should work").get(key).cloned()
+    }
+
+    fn set(&self, key: Key, value: Value) {
+        self.0.lock().expect("mutex should work").insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hashlink_lru::HashlinkLruCache;
+
+    cache_testkit::size_limited_cache_tests!(HashlinkLruCache::with_capacity);
+    cache_testkit::removable_cache_tests!(HashlinkLruCache::with_capacity);
+    cache_testkit::shareable_cache_tests!(HashlinkLruCache::with_capacity);
+}
@@ -0,0 +1,480 @@
+//! Stored `cache-bench` results, and comparisons between them that flag regressions.
+//!
+//! Each run is saved as JSON named after the git revision it measured. Comparing two runs matches
+//! up the same implementation, thread count and load mode in each, and uses Welch's t-test on the
+//! repeated measurements to tell a real change from run-to-run noise.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::load::LoadReport;
+
+/// The results of one `cache-bench` invocation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkRun {
+    /// The git revision that was measured, with `-dirty` if there were uncommitted changes.
+    pub revision: String,
+    /// The command line arguments, so that runs with different settings can be told apart.
+    pub arguments: Vec<String>,
+    /// Every repetition of every thread count, by implementation.
+    pub results: BTreeMap<String, Vec<LoadReport>>,
+}
+
+impl BenchmarkRun {
+    pub fn new(revision: String, arguments: Vec<String>, reports: Vec<LoadReport>) -> Self {
+        let mut results: BTreeMap<String, Vec<LoadReport>> = BTreeMap::new();
+        for report in reports {
+            results
+                .entry(report.implementation.clone())
+                .or_default()
+                .push(report);
+        }
+        Self {
+            revision,
+            arguments,
+            results,
+        }
+    }
+
+    /// Where the run for `revision` is kept in `directory`. The revision names a file in the
+    /// directory, so it can't contain a path separator.
+    pub fn path(directory: &Path, revision: &str) -> io::Result<PathBuf> {
+        if revision.is_empty() || revision.contains(['/', std::path::MAIN_SEPARATOR]) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("revision {revision:?} must be a name, not a path"),
+            ));
+        }
+        Ok(directory.join(format!("{revision}.json")))
+    }
+
+    /// Saves the run in `directory` under its revision, replacing any earlier run of the same
+    /// revision, and returns where it went.
+    pub fn save(&self, directory: &Path) -> io::Result<PathBuf> {
+        std::fs::create_dir_all(directory)?;
+        let path = Self::path(directory, &self.revision)?;
+        serde_json::to_writer_pretty(BufWriter::new(File::create(&path)?), self)
+            .map_err(io::Error::other)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = File::open(path).map_err(|error| {
+            io::Error::new(error.kind(), format!("{}: {error}", path.display()))
+        })?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+/// The current git revision's short hash, with `-dirty` if the working tree has changes.
+pub fn git_revision() -> io::Result<String> {
+    let git = |arguments: &[&str]| -> io::Result<String> {
+        let output = Command::new("git").args(arguments).output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "git {} failed: {}",
+                arguments.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let revision = git(&["rev-parse", "--short", "HEAD"])?;
+    if git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty() {
+        Ok(revision)
+    } else {
+        Ok(format!("{revision}-dirty"))
+    }
+}
+
+/// How one metric of one configuration changed between two runs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Comparison {
+    pub implementation: String,
+    pub threads: usize,
+    pub mode: String,
+    pub metric: &'static str,
+    /// The mean over the base run's repetitions.
+    pub base: f64,
+    /// The mean over the new run's repetitions. None when the new run didn't measure this
+    /// configuration.
+    pub new: Option<f64>,
+    /// `new / base - 1`.
+    pub change: Option<f64>,
+    /// The chance of a difference at least this big if nothing changed. None when either run
+    /// has fewer than two repetitions, which isn't enough to tell.
+    pub p_value: Option<f64>,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    Unchanged,
+    Improvement,
+    Regression,
+    TooFewSamples,
+    /// The base run measured this, but the new run didn't.
+    Missing,
+}
+
+impl Verdict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::Unchanged => "unchanged",
+            Verdict::Improvement => "improvement",
+            Verdict::Regression => "regression",
+            Verdict::TooFewSamples => "too-few-samples",
+            Verdict::Missing => "missing",
+        }
+    }
+}
+
+impl Comparison {
+    pub const CSV_HEADER: &'static str =
+        "implementation,threads,mode,metric,base,new,change,p_value,verdict";
+
+    /// Whether this is a change worth failing over, for better or worse.
+    pub fn is_significant(&self) -> bool {
+        matches!(self.verdict, Verdict::Improvement | Verdict::Regression)
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{:.4},{},{},{},{}",
+            self.implementation,
+            self.threads,
+            self.mode,
+            self.metric,
+            self.base,
+            self.new.map(|new| format!("{new:.4}")).unwrap_or_default(),
+            self.change
+                .map(|change| format!("{change:+.4}"))
+                .unwrap_or_default(),
+            self.p_value
+                .map(|p_value| format!("{p_value:.4}"))
+                .unwrap_or_default(),
+            self.verdict.name()
+        )
+    }
+}
+
+/// How sure, and how big, a change has to be to count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Significance {
+    /// The largest p-value that counts as a real change.
+    pub alpha: f64,
+    /// The smallest relative change that counts, however sure the test is. With enough
+    /// repetitions even a 0.1% change is "significant", but not worth failing a build over.
+    pub min_change: f64,
+}
+
+/// Compares throughput and hit ratio for every implementation, thread count and mode that the
+/// base run measured. Those the new run didn't measure are [Verdict::Missing].
+pub fn compare(
+    base: &BenchmarkRun,
+    new: &BenchmarkRun,
+    significance: Significance,
+) -> Vec<Comparison> {
+    let mut comparisons = Vec::new();
+    for (implementation, base_reports) in &base.results {
+        let new_reports = new
+            .results
+            .get(implementation)
+            .map_or(&[][..], Vec::as_slice);
+        let mut configurations: Vec<(usize, &str)> = base_reports
+            .iter()
+            .map(|report| (report.threads, report.mode.as_str()))
+            .collect();
+        configurations.sort();
+        configurations.dedup();
+        for (threads, mode) in configurations {
+            let samples = |reports: &[LoadReport], metric: Metric| -> Vec<f64> {
+                reports
+                    .iter()
+                    .filter(|report| report.threads == threads && report.mode == mode)
+                    .map(metric)
+                    .collect()
+            };
+            let metrics: [(&'static str, Metric); 2] = [
+                ("ops_per_second", |report| report.ops_per_second),
+                ("hit_ratio", |report| report.hit_ratio),
+            ];
+            for (metric, value) in metrics {
+                let (before, after) = (samples(base_reports, value), samples(new_reports, value));
+                let base_mean = mean(&before);
+                if after.is_empty() {
+                    comparisons.push(Comparison {
+                        implementation: implementation.clone(),
+                        threads,
+                        mode: mode.to_string(),
+                        metric,
+                        base: base_mean,
+                        new: None,
+                        change: None,
+                        p_value: None,
+                        verdict: Verdict::Missing,
+                    });
+                    continue;
+                }
+                let new_mean = mean(&after);
+                let change = if base_mean == 0.0 {
+                    0.0
+                } else {
+                    new_mean / base_mean - 1.0
+                };
+                let p_value = welch_p_value(&before, &after);
+                let verdict = match p_value {
+                    None => Verdict::TooFewSamples,
+                    Some(p_value)
+                        if p_value <= significance.alpha
+                            && significance.min_change <= change.abs() =>
+                    {
+                        // Both metrics are better when they're higher.
+                        if 0.0 < change {
+                            Verdict::Improvement
+                        } else {
+                            Verdict::Regression
+                        }
+                    }
+                    Some(_) => Verdict::Unchanged,
+                };
+                comparisons.push(Comparison {
+                    implementation: implementation.clone(),
+                    threads,
+                    mode: mode.to_string(),
+                    metric,
+                    base: base_mean,
+                    new: Some(new_mean),
+                    change: Some(change),
+                    p_value,
+                    verdict,
+                });
+            }
+        }
+    }
+    comparisons
+}
+
+/// Reads one metric out of a report.
+type Metric = fn(&LoadReport) -> f64;
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+fn variance(samples: &[f64]) -> f64 {
+    let mean = mean(samples);
+    samples
+        .iter()
+        .map(|sample| (sample - mean).powi(2))
+        .sum::<f64>()
+        / (samples.len() - 1) as f64
+}
+
+/// The two-sided p-value of Welch's t-test, which doesn't assume the two sets of samples vary by
+/// the same amount. None with fewer than two samples in either set.
+fn welch_p_value(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (a_error, b_error) = (variance(a) / a.len() as f64, variance(b) / b.len() as f64);
+    let standard_error = (a_error + b_error).sqrt();
+    let difference = mean(a) - mean(b);
+    if standard_error == 0.0 {
+        // Every sample was the same, as deterministic metrics can be.
+        return Some(if difference == 0.0 { 1.0 } else { 0.0 });
+    }
+    let t = difference / standard_error;
+    let degrees_of_freedom = (a_error + b_error).powi(2)
+        / (a_error.powi(2) / (a.len() - 1) as f64 + b_error.powi(2) / (b.len() - 1) as f64);
+    // The two-sided tail of Student's t distribution.
+    Some(regularized_incomplete_beta(
+        degrees_of_freedom / (degrees_of_freedom + t * t),
+        degrees_of_freedom / 2.0,
+        0.5,
+    ))
+}
+
+/// I_x(a, b), by the continued fraction in Numerical Recipes §6.4.
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if 1.0 <= x {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges quickly on this side; use the symmetry on the other.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - front * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+    for m in 1..300 {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        fraction *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let step = d * c;
+        fraction *= step;
+        if (step - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    fraction
+}
+
+/// ln Γ(x) for positive x, by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let mut series = 1.000_000_000_190_015;
+    for (i, coefficient) in COEFFICIENTS.iter().enumerate() {
+        series += coefficient / (x + 1.0 + i as f64);
+    }
+    let t = x + 5.5;
+    (x + 0.5) * t.ln() - t + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{
+        history::{compare, welch_p_value, BenchmarkRun, Significance, Verdict},
+        load::LoadReport,
+    };
+
+    fn report(implementation: &str, ops_per_second: f64, hit_ratio: f64) -> LoadReport {
+        LoadReport {
+            implementation: implementation.to_string(),
+            threads: 4,
+            mode: "closed".to_string(),
+            target_ops_per_second: None,
+            operations: 0,
+            seconds: 1.0,
+            ops_per_second,
+            hit_ratio,
+            get_p50_ns: 0,
+            get_p99_ns: 0,
+            get_p999_ns: 0,
+            set_p50_ns: 0,
+            set_p99_ns: 0,
+            set_p999_ns: 0,
+        }
+    }
+
+    #[test]
+    fn welch_matches_reference_values() {
+        // t = -5 with 8 degrees of freedom.
+        let p_value = welch_p_value(&[1.0, 2.0, 3.0, 4.0, 5.0], &[6.0, 7.0, 8.0, 9.0, 10.0])
+            .expect("enough samples");
+        assert!((p_value - 0.001_052_8).abs() < 1e-6, "{p_value}");
+        // t = -1.2247 with 4 degrees of freedom.
+        let p_value = welch_p_value(&[1.0, 2.0, 3.0], &[2.0, 3.0, 4.0]).expect("enough samples");
+        assert!((p_value - 0.287_9).abs() < 1e-4, "{p_value}");
+        assert_eq!(welch_p_value(&[1.0], &[2.0, 3.0]), None);
+    }
+
+    #[test]
+    fn flags_regressions_but_not_noise() {
+        let run = |revision: &str, reports: Vec<LoadReport>| {
+            BenchmarkRun::new(revision.to_string(), Vec::new(), reports)
+        };
+        let base = run(
+            "base",
+            vec![
+                report("example", 1000.0, 0.5),
+                report("example", 1010.0, 0.5),
+                report("example", 990.0, 0.5),
+                report("lru", 500.0, 0.7),
+                report("sieve", 900.0, 0.6),
+                LoadReport {
+                    threads: 8,
+                    ..report("lru", 400.0, 0.7)
+                },
+            ],
+        );
+        let new = run(
+            "new",
+            vec![
+                report("example", 800.0, 0.5),
+                report("example", 805.0, 0.5),
+                report("example", 795.0, 0.5),
+                report("lru", 600.0, 0.7),
+                report("clock", 700.0, 0.6),
+            ],
+        );
+        let comparisons = compare(
+            &base,
+            &new,
+            Significance {
+                alpha: 0.01,
+                min_change: 0.02,
+            },
+        );
+        let verdicts: Vec<(&str, &str, Verdict)> = comparisons
+            .iter()
+            .map(|comparison| {
+                (
+                    comparison.implementation.as_str(),
+                    comparison.metric,
+                    comparison.verdict,
+                )
+            })
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                ("example", "ops_per_second", Verdict::Regression),
+                ("example", "hit_ratio", Verdict::Unchanged),
+                ("lru", "ops_per_second", Verdict::TooFewSamples),
+                ("lru", "hit_ratio", Verdict::TooFewSamples),
+                ("lru", "ops_per_second", Verdict::Missing),
+                ("lru", "hit_ratio", Verdict::Missing),
+                ("sieve", "ops_per_second", Verdict::Missing),
+                ("sieve", "hit_ratio", Verdict::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn revisions_must_be_names() {
+        let history = Path::new("history");
+        assert_eq!(
+            BenchmarkRun::path(history, "1a2b3c4-dirty").expect("a name"),
+            history.join("1a2b3c4-dirty.json")
+        );
+        for revision in ["", "../x", "a/b"] {
+            BenchmarkRun::path(history, revision).expect_err(revision);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::hash::RandomState;
+
+use cache::{ShareableCache, SizeLimitedCache};
+
+pub struct KCache<Key, Value>(k_cache::Cache<Key, Value, RandomState>);
+
+impl<Key, Value> KCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    Value: Clone,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(cache::MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(k_cache::Cache::new(RandomState::new(), capacity))
+    }
+}
+
+impl<Key, Value> Default for KCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for KCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0.get(key).cloned()
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0.put(key, value);
+    }
+}
+
+/// An internally-shareable cache that implements "internal mutability."
+pub struct SharableKCache<Key, Value>(k_cache::SegmentedCache<Key, Value, RandomState>);
+
+impl<Key, Value> SharableKCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    Value: Clone,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(cache::MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        // Each segment gets an equal share of the capacity, so tiny caches get fewer segments.
+        Self(k_cache::SegmentedCache::new(
+            capacity.clamp(1, 16),
+            capacity,
+        ))
+    }
+}
+
+impl<Key, Value> Default for SharableKCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> ShareableCache<Key, Value> for SharableKCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send,
+    Value: Clone + Send,
+{
+    fn get(&self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&self, key: Key, value: Value) {
+        self.0.put(key, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache_testkit::{Capacity, Expectations};
+
+    use crate::kcache::{KCache, SharableKCache};
+
+    // k-cache counts an overwritten key twice until the sieve hand passes its old entry, so it
+    // can hold fewer keys than its capacity. It also needs room for at least one entry.
+    const EXPECTATIONS: Expectations = Expectations {
+        capacity: Capacity::AtMost,
+        zero_capacity: false,
+        ..Expectations::STRICT
+    };
+
+    cache_testkit::size_limited_cache_tests!(KCache::with_capacity, EXPECTATIONS);
+    // k-cache can't remove a key or clear the cache, so KCache isn't a RemovableCache.
+    // Each segment also gets an even share of the capacity, and keys don't spread evenly.
+    cache_testkit::shareable_cache_tests!(SharableKCache::with_capacity, EXPECTATIONS);
+}
@@ -0,0 +1,262 @@
+//! Per-operation latency recording, for the tails that a mean hides.
+//!
+//! Criterion reports the mean time per iteration, so a lock convoy or an eviction burst that
+//! stalls one operation in a thousand barely moves it. Here every operation is timed on its own
+//! and recorded in an HDR histogram, with gets and sets kept apart.
+
+use std::{
+    io,
+    sync::Barrier,
+    time::{Duration, Instant},
+};
+
+use cache::ShareableCache;
+use hdrhistogram::{
+    serialization::{
+        interval_log::{IntervalLogWriterBuilder, Tag},
+        V2Serializer,
+    },
+    Histogram,
+};
+use serde::Serialize;
+
+use crate::{trace::Op, workload::WorkloadSpec};
+
+/// The longest latency the histograms can tell apart from "even longer", in nanoseconds.
+const HIGHEST_TRACKABLE_NANOSECONDS: u64 = 60_000_000_000;
+
+/// How many distinct requests each thread cycles through.
+const REQUESTS_PER_THREAD: usize = 100 * cache::MAX_SIZE;
+
+/// Latency histograms in nanoseconds, for each kind of operation.
+#[derive(Debug, Clone)]
+pub struct OperationLatencies {
+    pub get: Histogram<u64>,
+    pub set: Histogram<u64>,
+    /// The wall clock time the measured operations took, across all threads.
+    pub elapsed: Duration,
+}
+
+impl OperationLatencies {
+    pub fn new() -> Self {
+        let histogram = || {
+            Histogram::new_with_bounds(1, HIGHEST_TRACKABLE_NANOSECONDS, 3)
+                .expect("histogram bounds are valid")
+        };
+        Self {
+            get: histogram(),
+            set: histogram(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    fn add(&mut self, other: &OperationLatencies) {
+        self.get
+            .add(&other.get)
+            .expect("histograms have the same bounds");
+        self.set
+            .add(&other.set)
+            .expect("histograms have the same bounds");
+    }
+}
+
+impl Default for OperationLatencies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs a workload against a cache from `thread_count` threads at once, timing every operation.
+///
+/// Each thread replays its own seeded stream of the workload: once to warm the cache up, and then
+/// `operations_per_thread` timed operations. Gets are plain gets; there's no read-through here,
+/// so that each histogram holds only one kind of operation.
+pub fn record_latencies(
+    cache: &impl ShareableCache<String, String>,
+    thread_count: usize,
+    operations_per_thread: usize,
+    workload: &WorkloadSpec,
+) -> OperationLatencies {
+    let barrier = Barrier::new(thread_count + 1);
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..thread_count)
+            .map(|thread_index| {
+                let workload = workload
+                    .clone()
+                    .with_seed(workload.seed + thread_index as u64);
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    let requests = workload.keyed_requests(REQUESTS_PER_THREAD);
+                    for (_, key) in &requests {
+                        cache.set(key.clone(), key.clone());
+                    }
+                    barrier.wait();
+
+                    let mut latencies = OperationLatencies::new();
+                    for (request, key) in requests.iter().cycle().take(operations_per_thread) {
+                        match request.op {
+                            Op::Get => {
+                                let start = Instant::now();
+                                std::hint::black_box(cache.get(key));
+                                let nanoseconds = start.elapsed().as_nanos() as u64;
+                                latencies.get.saturating_record(nanoseconds.max(1));
+                            }
+                            Op::Set | Op::Delete => {
+                                let (key, value) = (key.clone(), key.clone());
+                                let start = Instant::now();
+                                cache.set(key, value);
+                                let nanoseconds = start.elapsed().as_nanos() as u64;
+                                latencies.set.saturating_record(nanoseconds.max(1));
+                            }
+                        }
+                    }
+                    latencies
+                })
+            })
+            .collect();
+
+        barrier.wait();
+        let start = Instant::now();
+        let mut total = OperationLatencies::new();
+        for thread in threads {
+            total.add(&thread.join().expect("benchmark threads don't panic"));
+        }
+        total.elapsed = start.elapsed();
+        total
+    })
+}
+
+/// The headline percentiles of one histogram.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySummary {
+    pub implementation: String,
+    pub threads: usize,
+    pub operation: &'static str,
+    pub count: u64,
+    pub p50_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+}
+
+impl LatencySummary {
+    pub const CSV_HEADER: &'static str =
+        "implementation,threads,operation,count,p50_ns,p99_ns,p999_ns,max_ns";
+
+    pub fn new(
+        implementation: &str,
+        threads: usize,
+        operation: &'static str,
+        histogram: &Histogram<u64>,
+    ) -> Self {
+        Self {
+            implementation: implementation.to_string(),
+            threads,
+            operation,
+            count: histogram.len(),
+            p50_ns: histogram.value_at_quantile(0.5),
+            p99_ns: histogram.value_at_quantile(0.99),
+            p999_ns: histogram.value_at_quantile(0.999),
+            max_ns: histogram.max(),
+        }
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.implementation,
+            self.threads,
+            self.operation,
+            self.count,
+            self.p50_ns,
+            self.p99_ns,
+            self.p999_ns,
+            self.max_ns
+        )
+    }
+}
+
+/// Writes tagged histograms to an HdrHistogram interval log, which HdrHistogram's own tools, like
+/// HistogramLogAnalyzer, can read. Each histogram gets its own interval, laid end to end.
+///
+/// Tags can't contain spaces or commas.
+pub fn write_interval_log(
+    writer: &mut impl io::Write,
+    histograms: &[(String, &Histogram<u64>, Duration)],
+) -> io::Result<()> {
+    let mut serializer = V2Serializer::new();
+    let mut log = IntervalLogWriterBuilder::new()
+        .add_comment("Cache operation latencies in nanoseconds")
+        .begin_log_with(writer, &mut serializer)?;
+    let mut start = Duration::ZERO;
+    for (tag, histogram, duration) in histograms {
+        let tag = Tag::new(tag).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{tag:?} can't contain spaces or commas"),
+            )
+        })?;
+        log.write_histogram(histogram, start, *duration, Some(tag))
+            .map_err(|error| io::Error::other(error.to_string()))?;
+        start += *duration;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use cache::synchronized_cache;
+    use hdrhistogram::serialization::interval_log::{IntervalLogIterator, LogEntry};
+
+    use crate::{
+        latency::{record_latencies, write_interval_log, LatencySummary},
+        workload::{KeyDistribution, WorkloadSpec},
+    };
+
+    #[test]
+    fn every_operation_is_recorded() {
+        let cache = synchronized_cache(example_sieve_cache::SieveCache::new());
+        let workload =
+            WorkloadSpec::new(KeyDistribution::Uniform { key_space: 200 }).with_read_ratio(0.5);
+        let latencies = record_latencies(&cache, 2, 1_000, &workload);
+        assert_eq!(latencies.get.len() + latencies.set.len(), 2_000);
+        assert!(400 < latencies.set.len() && latencies.set.len() < 1_600);
+
+        let summary = LatencySummary::new("example", 2, "get", &latencies.get);
+        assert!(summary.p50_ns <= summary.p99_ns);
+        assert!(summary.p99_ns <= summary.p999_ns);
+        assert!(summary.p999_ns <= summary.max_ns);
+    }
+
+    #[test]
+    fn interval_log_round_trip() {
+        let cache = synchronized_cache(example_sieve_cache::SieveCache::new());
+        let workload = WorkloadSpec::new(KeyDistribution::Uniform { key_space: 200 });
+        let latencies = record_latencies(&cache, 1, 100, &workload);
+
+        let mut log = Vec::new();
+        write_interval_log(
+            &mut log,
+            &[(
+                "example/1/get".to_string(),
+                &latencies.get,
+                Duration::from_secs(1),
+            )],
+        )
+        .expect("writing to a Vec works");
+        let tags: Vec<String> = IntervalLogIterator::new(&log)
+            .filter_map(|entry| match entry {
+                Ok(LogEntry::Interval(interval)) => {
+                    interval.tag().map(|tag| tag.as_str().to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tags, vec!["example/1/get"]);
+
+        let bad_tag = [("has space".to_string(), &latencies.get, Duration::ZERO)];
+        assert!(write_interval_log(&mut Vec::new(), &bad_tag).is_err());
+    }
+}
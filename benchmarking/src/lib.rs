@@ -1 +1,17 @@
-
+pub mod hashlink_lru;
+pub mod history;
+pub mod kcache;
+pub mod latency;
+pub mod load;
+pub mod lru_cache;
+pub mod lru_crate;
+pub mod memory;
+pub mod minimoka;
+pub mod mrc;
+pub mod plot;
+pub mod policy;
+pub mod quickcache;
+pub mod simulator;
+pub mod source;
+pub mod trace;
+pub mod workload;
@@ -0,0 +1,350 @@
+//! Live load against a shared cache from many threads for a fixed time.
+//!
+//! Closed-loop load has each thread issue its next operation as soon as the last one returns, so
+//! it measures how fast the cache can go. Open-loop load issues operations on a fixed schedule
+//! whether or not the cache keeps up, like independent clients would, and times each operation
+//! from when it was due rather than when it started. A stall then shows up in the latency of
+//! every operation that queued behind it, instead of quietly lowering the request rate.
+
+use std::{
+    sync::Barrier,
+    time::{Duration, Instant},
+};
+
+use cache::ShareableCache;
+use serde::{Deserialize, Serialize};
+
+use crate::{latency::OperationLatencies, trace::Op, workload::WorkloadSpec};
+
+/// How operations are paced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadMode {
+    /// Each thread issues operations back to back.
+    Closed,
+    /// The threads together issue `operations_per_second`, evenly spaced.
+    Open { operations_per_second: f64 },
+}
+
+impl LoadMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoadMode::Closed => "closed",
+            LoadMode::Open { .. } => "open",
+        }
+    }
+}
+
+/// How to drive the load.
+#[derive(Debug, Clone)]
+pub struct LoadSpec {
+    pub threads: usize,
+    /// How long to run before measuring, to fill the cache.
+    pub warmup: Duration,
+    /// How long to measure for.
+    pub duration: Duration,
+    pub mode: LoadMode,
+    /// Whether a get that misses sets the value, like a cache in front of a database would.
+    pub read_through: bool,
+    /// How many requests each thread generates up front and cycles through.
+    pub requests_per_thread: usize,
+}
+
+/// What happened during the measured part of a run.
+#[derive(Debug, Clone)]
+pub struct LoadResult {
+    pub operations: u64,
+    pub gets: u64,
+    pub hits: u64,
+    /// Sets done by read-through on a miss are recorded with the sets.
+    pub latencies: OperationLatencies,
+}
+
+impl LoadResult {
+    /// Operations per second, not counting read-through sets.
+    pub fn throughput(&self) -> f64 {
+        self.operations as f64 / self.latencies.elapsed.as_secs_f64()
+    }
+
+    pub fn hit_ratio(&self) -> f64 {
+        if self.gets == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.gets as f64
+        }
+    }
+}
+
+/// Runs a workload against a cache from `spec.threads` threads, for the warmup and then the
+/// measured duration.
+///
+/// Each thread replays its own seeded stream of the workload, with values of each request's size.
+pub fn run_load(
+    cache: &impl ShareableCache<String, String>,
+    spec: &LoadSpec,
+    workload: &WorkloadSpec,
+) -> LoadResult {
+    let barrier = Barrier::new(spec.threads);
+    // Each thread's share of the schedule. Staggering the threads' first operations spreads them
+    // evenly over the interval too.
+    let interval = match spec.mode {
+        LoadMode::Closed => Duration::ZERO,
+        LoadMode::Open {
+            operations_per_second,
+        } => Duration::from_secs_f64(spec.threads as f64 / operations_per_second),
+    };
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..spec.threads)
+            .map(|thread_index| {
+                let workload = workload
+                    .clone()
+                    .with_seed(workload.seed + thread_index as u64);
+                let barrier = &barrier;
+                scope.spawn(move || {
+                    let requests = workload.keyed_requests(spec.requests_per_thread);
+                    let largest = requests.iter().map(|(request, _)| request.size).max();
+                    let values = "v".repeat(largest.unwrap_or(0) as usize);
+                    barrier.wait();
+
+                    let start = Instant::now();
+                    let measure_from = start + spec.warmup;
+                    let measure_until = measure_from + spec.duration;
+                    let offset = interval.mul_f64(thread_index as f64 / spec.threads as f64);
+                    let mut result = LoadResult {
+                        operations: 0,
+                        gets: 0,
+                        hits: 0,
+                        latencies: OperationLatencies::new(),
+                    };
+                    let mut due = start + offset;
+                    for (request, key) in requests.iter().cycle() {
+                        let began = match spec.mode {
+                            LoadMode::Closed => Instant::now(),
+                            LoadMode::Open { .. } => {
+                                wait_until(due);
+                                let began = due;
+                                due += interval;
+                                began
+                            }
+                        };
+                        // An overloaded open-loop run stops on time too, dropping its backlog.
+                        if began >= measure_until || Instant::now() >= measure_until {
+                            break;
+                        }
+                        let measured = began >= measure_from;
+                        let value = || values[..request.size as usize].to_string();
+                        match request.op {
+                            Op::Get => {
+                                let hit = cache.get(key).is_some();
+                                let finished = Instant::now();
+                                let missed_set = !hit && spec.read_through;
+                                if missed_set {
+                                    cache.set(key.clone(), value());
+                                }
+                                if measured {
+                                    result.gets += 1;
+                                    result.hits += u64::from(hit);
+                                    record(&mut result.latencies.get, finished - began);
+                                    if missed_set {
+                                        record(&mut result.latencies.set, finished.elapsed());
+                                    }
+                                }
+                            }
+                            Op::Set | Op::Delete => {
+                                cache.set(key.clone(), value());
+                                if measured {
+                                    record(&mut result.latencies.set, began.elapsed());
+                                }
+                            }
+                        }
+                        result.operations += u64::from(measured);
+                    }
+                    result.latencies.elapsed = measure_until.min(Instant::now()) - measure_from;
+                    result
+                })
+            })
+            .collect();
+
+        let mut total = LoadResult {
+            operations: 0,
+            gets: 0,
+            hits: 0,
+            latencies: OperationLatencies::new(),
+        };
+        for thread in threads {
+            let result = thread.join().expect("load threads don't panic");
+            total.operations += result.operations;
+            total.gets += result.gets;
+            total.hits += result.hits;
+            total
+                .latencies
+                .get
+                .add(&result.latencies.get)
+                .expect("histograms have the same bounds");
+            total
+                .latencies
+                .set
+                .add(&result.latencies.set)
+                .expect("histograms have the same bounds");
+            total.latencies.elapsed = total.latencies.elapsed.max(result.latencies.elapsed);
+        }
+        total
+    })
+}
+
+fn record(histogram: &mut hdrhistogram::Histogram<u64>, latency: Duration) {
+    histogram.saturating_record((latency.as_nanos() as u64).max(1));
+}
+
+/// Sleeps through long waits and yields through short ones, which sleeping would overshoot.
+/// Yielding rather than spinning leaves the CPU to other load threads when there are more threads
+/// than cores.
+fn wait_until(due: Instant) {
+    loop {
+        let now = Instant::now();
+        if now >= due {
+            return;
+        }
+        let remaining = due - now;
+        if remaining > Duration::from_millis(2) {
+            std::thread::sleep(remaining - Duration::from_millis(1));
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// One line of `cache-bench` output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadReport {
+    pub implementation: String,
+    pub threads: usize,
+    pub mode: String,
+    /// The requested rate in open-loop mode.
+    pub target_ops_per_second: Option<f64>,
+    pub operations: u64,
+    pub seconds: f64,
+    pub ops_per_second: f64,
+    pub hit_ratio: f64,
+    pub get_p50_ns: u64,
+    pub get_p99_ns: u64,
+    pub get_p999_ns: u64,
+    pub set_p50_ns: u64,
+    pub set_p99_ns: u64,
+    pub set_p999_ns: u64,
+}
+
+impl LoadReport {
+    pub const CSV_HEADER: &'static str = "implementation,threads,mode,target_ops_per_second,operations,seconds,ops_per_second,hit_ratio,get_p50_ns,get_p99_ns,get_p999_ns,set_p50_ns,set_p99_ns,set_p999_ns";
+
+    pub fn new(implementation: &str, spec: &LoadSpec, result: &LoadResult) -> Self {
+        let (get, set) = (&result.latencies.get, &result.latencies.set);
+        Self {
+            implementation: implementation.to_string(),
+            threads: spec.threads,
+            mode: spec.mode.name().to_string(),
+            target_ops_per_second: match spec.mode {
+                LoadMode::Closed => None,
+                LoadMode::Open {
+                    operations_per_second,
+                } => Some(operations_per_second),
+            },
+            operations: result.operations,
+            seconds: result.latencies.elapsed.as_secs_f64(),
+            ops_per_second: result.throughput(),
+            hit_ratio: result.hit_ratio(),
+            get_p50_ns: get.value_at_quantile(0.5),
+            get_p99_ns: get.value_at_quantile(0.99),
+            get_p999_ns: get.value_at_quantile(0.999),
+            set_p50_ns: set.value_at_quantile(0.5),
+            set_p99_ns: set.value_at_quantile(0.99),
+            set_p999_ns: set.value_at_quantile(0.999),
+        }
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.3},{:.0},{:.4},{},{},{},{},{},{}",
+            self.implementation,
+            self.threads,
+            self.mode,
+            self.target_ops_per_second
+                .map(|rate| rate.to_string())
+                .unwrap_or_default(),
+            self.operations,
+            self.seconds,
+            self.ops_per_second,
+            self.hit_ratio,
+            self.get_p50_ns,
+            self.get_p99_ns,
+            self.get_p999_ns,
+            self.set_p50_ns,
+            self.set_p99_ns,
+            self.set_p999_ns
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use cache::synchronized_cache;
+
+    use crate::{
+        load::{run_load, LoadMode, LoadReport, LoadSpec},
+        workload::{KeyDistribution, WorkloadSpec},
+    };
+
+    fn spec(threads: usize, mode: LoadMode) -> LoadSpec {
+        LoadSpec {
+            threads,
+            warmup: Duration::from_millis(20),
+            duration: Duration::from_millis(100),
+            mode,
+            read_through: true,
+            requests_per_thread: 10_000,
+        }
+    }
+
+    #[test]
+    fn closed_loop_reads_through() {
+        let cache = synchronized_cache(example_sieve_cache::SieveCache::with_capacity(1000));
+        let workload = WorkloadSpec::new(KeyDistribution::Uniform { key_space: 500 });
+        let spec = spec(2, LoadMode::Closed);
+        let result = run_load(&cache, &spec, &workload);
+        assert!(0 < result.operations);
+        assert_eq!(result.gets, result.operations);
+        // Everything fits, so after the warmup nearly every get hits.
+        assert!(0.99 < result.hit_ratio(), "{}", result.hit_ratio());
+
+        let report = LoadReport::new("example", &spec, &result);
+        assert_eq!(
+            report.to_csv_row().split(',').count(),
+            LoadReport::CSV_HEADER.split(',').count()
+        );
+    }
+
+    #[test]
+    fn open_loop_keeps_to_the_rate() {
+        let cache = synchronized_cache(example_sieve_cache::SieveCache::with_capacity(100));
+        let workload =
+            WorkloadSpec::new(KeyDistribution::Uniform { key_space: 500 }).with_read_ratio(0.5);
+        let result = run_load(
+            &cache,
+            &spec(
+                2,
+                LoadMode::Open {
+                    operations_per_second: 10_000.0,
+                },
+            ),
+            &workload,
+        );
+        // 10k per second for a tenth of a second.
+        assert!(
+            900 <= result.operations && result.operations <= 1_100,
+            "{}",
+            result.operations
+        );
+        assert!(!result.latencies.set.is_empty());
+    }
+}
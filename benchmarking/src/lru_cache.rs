@@ -0,0 +1,132 @@
+use cache::{RemovableCache, ShareableCache, SizeLimitedCache, MAX_SIZE};
+
+pub struct LruCache<Key, Value>(moka::sync::Cache<Key, Value>);
+
+impl<Key, Value> LruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(moka::sync::Cache::new(capacity as u64))
+    }
+}
+
+impl<Key, Value> Default for LruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for LruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0.insert(key, value);
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for LruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        self.0.remove(key)
+    }
+
+    fn clear(&mut self) {
+        self.0.invalidate_all();
+    }
+}
+
+impl<Key, Value> ShareableCache<Key, Value> for LruCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn get(&self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&self, key: Key, value: Value) {
+        self.0.insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::{RemovableCache, ShareableCache, SizeLimitedCache};
+    use cache_testkit::Expectations;
+
+    use crate::lru_cache::LruCache;
+
+    /// moka evicts in the background, so for a moment after a set it can hold more than its
+    /// capacity. This runs its pending work after every set, remove and clear, to check where it
+    /// settles.
+    struct Settled(LruCache<u64, u64>);
+
+    impl Settled {
+        fn with_capacity(capacity: usize) -> Self {
+            Self(LruCache::with_capacity(capacity))
+        }
+    }
+
+    impl SizeLimitedCache<u64, u64> for Settled {
+        fn get(&mut self, key: &u64) -> Option<u64> {
+            ShareableCache::get(self, key)
+        }
+
+        fn set(&mut self, key: u64, value: u64) {
+            ShareableCache::set(self, key, value);
+        }
+    }
+
+    impl RemovableCache<u64, u64> for Settled {
+        fn remove(&mut self, key: &u64) -> Option<u64> {
+            let value = self.0.remove(key);
+            self.0 .0.run_pending_tasks();
+            value
+        }
+
+        fn clear(&mut self) {
+            self.0.clear();
+            self.0 .0.run_pending_tasks();
+        }
+    }
+
+    impl ShareableCache<u64, u64> for Settled {
+        fn get(&self, key: &u64) -> Option<u64> {
+            self.0 .0.get(key)
+        }
+
+        fn set(&self, key: u64, value: u64) {
+            self.0 .0.insert(key, value);
+            self.0 .0.run_pending_tasks();
+        }
+    }
+
+    // TinyLFU admission can turn a new key away rather than evict a more popular one for it.
+    const EXPECTATIONS: Expectations = Expectations {
+        admits_every_key: false,
+        ..Expectations::STRICT
+    };
+
+    cache_testkit::size_limited_cache_tests!(Settled::with_capacity, EXPECTATIONS);
+    cache_testkit::removable_cache_tests!(Settled::with_capacity, EXPECTATIONS);
+    cache_testkit::shareable_cache_tests!(Settled::with_capacity, EXPECTATIONS);
+}
@@ -0,0 +1,93 @@
+use std::{num::NonZeroUsize, sync::Mutex};
+
+use cache::{RemovableCache, ShareableCache, SizeLimitedCache, MAX_SIZE};
+
+/// The `lru` crate's cache. It isn't thread-safe on its own, so sharing it takes a mutex. Used
+/// as a `SizeLimitedCache`, the mutex is never locked.
+pub struct LruCrateCache<Key, Value>(Mutex<lru::LruCache<Key, Value>>);
+
+impl<Key, Value> LruCrateCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).expect("capacity must be at least 1");
+        Self(Mutex::new(lru::LruCache::new(capacity)))
+    }
+}
+
+impl<Key, Value> Default for LruCrateCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for LruCrateCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0
+            .get_mut()
+            .expect("mutex should work")
+            .get(key)
+            .cloned()
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0.get_mut().expect("mutex should work").put(key, value);
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for LruCrateCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        self.0.get_mut().expect("mutex should work").pop(key)
+    }
+
+    fn clear(&mut self) {
+        self.0.get_mut().expect("mutex should work").clear();
+    }
+}
+
+impl<Key, Value> ShareableCache<Key, Value> for LruCrateCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Send,
+    Value: Clone + Send,
+{
+    fn get(&self, key: &Key) -> Option<Value> {
+        self.0.lock().expect("mutex should work").get(key).cloned()
+    }
+
+    fn set(&self, key: Key, value: Value) {
+        self.0.lock().expect("mutex should work").put(key, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache_testkit::Expectations;
+
+    use crate::lru_crate::LruCrateCache;
+
+    // The lru crate needs room for at least one entry.
+    const EXPECTATIONS: Expectations = Expectations {
+        zero_capacity: false,
+        ..Expectations::STRICT
+    };
+
+    cache_testkit::size_limited_cache_tests!(LruCrateCache::with_capacity, EXPECTATIONS);
+    cache_testkit::removable_cache_tests!(LruCrateCache::with_capacity, EXPECTATIONS);
+    cache_testkit::shareable_cache_tests!(LruCrateCache::with_capacity, EXPECTATIONS);
+}
@@ -0,0 +1,314 @@
+//! Heap footprint and allocation counts for each cache implementation.
+//!
+//! A [CountingAllocator] wraps the system allocator and counts the current thread's allocations
+//! and live heap bytes. [measure_memory] fills a cache to capacity with fixed-size keys and values
+//! and reads the counts before and after.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::Arc,
+};
+
+use cache::SizeLimitedCache;
+use cache_policies::next_use_indices;
+use serde::Serialize;
+
+use crate::policy::{CacheVisitor, Policy};
+
+/// Counts allocations on top of the system allocator. It only counts once a binary installs it:
+/// ```
+/// use benchmarking::memory::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// # fn main() {}
+/// ```
+///
+/// The counts are per thread, so other threads' work doesn't leak into a measurement.
+pub struct CountingAllocator;
+
+thread_local! {
+    // Const-initialized cells with no destructor: using them never allocates, which matters
+    // inside an allocator.
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+    static BYTES_IN_USE: Cell<i64> = const { Cell::new(0) };
+}
+
+fn count(allocations: u64, bytes: i64) {
+    // During thread teardown the counters may already be gone; those allocations go uncounted.
+    let _ = ALLOCATIONS.try_with(|counter| counter.set(counter.get() + allocations));
+    let _ = BYTES_IN_USE.try_with(|counter| counter.set(counter.get() + bytes));
+}
+
+// SAFETY: every call is forwarded to the system allocator unchanged. The counting around it
+// doesn't allocate.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            count(1, layout.size() as i64);
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            count(1, layout.size() as i64);
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        count(0, -(layout.size() as i64));
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            count(1, new_size as i64 - layout.size() as i64);
+        }
+        new_pointer
+    }
+}
+
+/// This thread's allocation counts so far. All zeros unless [CountingAllocator] is installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AllocationStats {
+    /// Allocations and reallocations.
+    pub allocations: u64,
+    /// Bytes allocated and not yet freed. Freeing memory another thread allocated can make this
+    /// negative.
+    pub bytes_in_use: i64,
+}
+
+impl AllocationStats {
+    pub fn now() -> Self {
+        Self {
+            allocations: ALLOCATIONS.with(Cell::get),
+            bytes_in_use: BYTES_IN_USE.with(Cell::get),
+        }
+    }
+
+    /// What happened between `earlier` and this.
+    pub fn since(self, earlier: AllocationStats) -> AllocationStats {
+        AllocationStats {
+            allocations: self.allocations - earlier.allocations,
+            bytes_in_use: self.bytes_in_use - earlier.bytes_in_use,
+        }
+    }
+}
+
+/// How much memory a cache full of fixed-size entries takes, and how often it allocates.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemoryReport {
+    pub policy: &'static str,
+    pub capacity: usize,
+    /// How many of the entries the cache was filled with it still holds.
+    pub entries: usize,
+    pub key_size: usize,
+    pub value_size: usize,
+    /// All of the cache's heap bytes, including its keys and values, per entry held.
+    pub bytes_per_entry: f64,
+    /// The heap bytes per entry beyond the key's and value's own bytes.
+    pub overhead_bytes_per_entry: f64,
+    /// Allocations per `set` while filling the empty cache.
+    pub allocations_per_insert: f64,
+    /// Allocations per `get` of a key the cache was filled with, including cloning the value out.
+    pub allocations_per_get: f64,
+    /// Allocations per `set` of a new key into the full cache.
+    pub allocations_per_eviction: f64,
+}
+
+impl MemoryReport {
+    pub const CSV_HEADER: &'static str = "policy,capacity,entries,key_size,value_size,bytes_per_entry,overhead_bytes_per_entry,allocations_per_insert,allocations_per_get,allocations_per_eviction";
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.1},{:.1},{:.3},{:.3},{:.3}",
+            self.policy,
+            self.capacity,
+            self.entries,
+            self.key_size,
+            self.value_size,
+            self.bytes_per_entry,
+            self.overhead_bytes_per_entry,
+            self.allocations_per_insert,
+            self.allocations_per_get,
+            self.allocations_per_eviction
+        )
+    }
+}
+
+/// Fills a new cache with `capacity` entries of `String` keys and values of the given sizes,
+/// gets each of them, and then sets `capacity` new keys to push them out.
+///
+/// The numbers are only meaningful when [CountingAllocator] is the global allocator.
+pub fn measure_memory(
+    policy: Policy,
+    capacity: usize,
+    key_size: usize,
+    value_size: usize,
+) -> MemoryReport {
+    // Everything the cache is given is allocated before measuring starts, so that only the
+    // cache's own allocations are counted.
+    let key = |index: usize| {
+        let mut key = format!("{index:0key_size$}");
+        key.shrink_to_fit();
+        key
+    };
+    let entry = |index: usize| (key(index), "v".repeat(value_size));
+    let measurement = Measurement {
+        fill: (0..capacity).map(entry).collect(),
+        lookups: (0..capacity).map(key).collect(),
+        replacements: (capacity..2 * capacity).map(entry).collect(),
+    };
+    let next_uses: Arc<[usize]> = next_use_indices(
+        (0..capacity)
+            .chain(0..capacity)
+            .chain(capacity..2 * capacity),
+    )
+    .into();
+    // The entries' own heap bytes. Keys can be longer than `key_size` if it's too small to hold
+    // every index.
+    let payload_bytes: usize = measurement
+        .fill
+        .iter()
+        .map(|(key, value)| key.len() + value.len())
+        .sum();
+
+    let before = AllocationStats::now();
+    let measured = policy.build(capacity, move || next_uses, measurement);
+    let fill = measured.filled.since(before);
+    let lookups = measured.looked_up.since(measured.filled);
+    let replacements = measured.replaced.since(measured.looked_up);
+
+    // Entries the cache dropped while filling up were freed, so their bytes are already taken
+    // off the change in bytes in use. Adding back every entry's bytes leaves the cache's total.
+    let total_bytes = fill.bytes_in_use as f64 + payload_bytes as f64;
+    let per = |amount: f64, count: usize| {
+        if count == 0 {
+            0.0
+        } else {
+            amount / count as f64
+        }
+    };
+    let bytes_per_entry = per(total_bytes, measured.entries);
+    MemoryReport {
+        policy: policy.name(),
+        capacity,
+        entries: measured.entries,
+        key_size,
+        value_size,
+        bytes_per_entry,
+        overhead_bytes_per_entry: if measured.entries == 0 {
+            0.0
+        } else {
+            bytes_per_entry - per(payload_bytes as f64, capacity)
+        },
+        allocations_per_insert: per(fill.allocations as f64, capacity),
+        allocations_per_get: per(lookups.allocations as f64, capacity),
+        allocations_per_eviction: per(replacements.allocations as f64, capacity),
+    }
+}
+
+struct Measurement {
+    fill: Vec<(String, String)>,
+    lookups: Vec<String>,
+    replacements: Vec<(String, String)>,
+}
+
+struct Measured {
+    entries: usize,
+    filled: AllocationStats,
+    looked_up: AllocationStats,
+    replaced: AllocationStats,
+}
+
+impl CacheVisitor<String, String> for Measurement {
+    type Output = Measured;
+
+    fn visit(mut self, mut cache: impl SizeLimitedCache<String, String> + Send) -> Measured {
+        // Draining keeps the vectors' own buffers until the end, so freeing them isn't counted.
+        for (key, value) in self.fill.drain(..) {
+            cache.set(key, value);
+        }
+        let filled = AllocationStats::now();
+
+        let mut entries = 0;
+        for key in &self.lookups {
+            entries += usize::from(cache.get(key).is_some());
+        }
+        let looked_up = AllocationStats::now();
+
+        for (key, value) in self.replacements.drain(..) {
+            cache.set(key, value);
+        }
+        let replaced = AllocationStats::now();
+
+        Measured {
+            entries,
+            filled,
+            looked_up,
+            replaced,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        memory::{measure_memory, AllocationStats, CountingAllocator},
+        policy::Policy,
+    };
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn counts_this_threads_allocations() {
+        let before = AllocationStats::now();
+        let buffer = vec![0_u8; 1000];
+        let during = AllocationStats::now().since(before);
+        drop(buffer);
+        let after = AllocationStats::now().since(before);
+        assert_eq!(
+            during,
+            AllocationStats {
+                allocations: 1,
+                bytes_in_use: 1000
+            }
+        );
+        assert_eq!(after.bytes_in_use, 0);
+    }
+
+    #[test]
+    fn example_cache_footprint() {
+        let report = measure_memory(Policy::Example, 1000, 16, 64);
+        assert_eq!(report.entries, 1000);
+        assert!(80.0 < report.bytes_per_entry, "{report:?}");
+        assert!(0.0 < report.overhead_bytes_per_entry, "{report:?}");
+        // Getting a String value out clones it.
+        assert!(1.0 <= report.allocations_per_get, "{report:?}");
+    }
+
+    #[test]
+    fn every_policy_holds_what_fits() {
+        for policy in Policy::ALL {
+            if policy == Policy::Workshop {
+                // The workshop cache is yours to write!
+                continue;
+            }
+            let report = measure_memory(policy, 500, 8, 8);
+            if policy == Policy::QuickCache {
+                // Its capacity is a target, and it settles a little under it.
+                assert!(450 <= report.entries, "{report:?}");
+            } else {
+                assert_eq!(report.entries, 500, "{policy:?}");
+            }
+            assert!(16.0 < report.bytes_per_entry, "{report:?}");
+        }
+    }
+}
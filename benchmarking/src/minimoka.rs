@@ -0,0 +1,84 @@
+use cache::{RemovableCache, ShareableCache, SizeLimitedCache, MAX_SIZE};
+
+/// The mini-moka crate's concurrent cache, a lighter version of moka with the same TinyLFU
+/// admission and LRU eviction.
+pub struct MiniMokaCache<Key, Value>(mini_moka::sync::Cache<Key, Value>);
+
+impl<Key, Value> MiniMokaCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(mini_moka::sync::Cache::new(capacity as u64))
+    }
+}
+
+impl<Key, Value> Default for MiniMokaCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for MiniMokaCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0.insert(key, value);
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for MiniMokaCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        // mini-moka's invalidate doesn't return the value, so look it up first. That counts as an
+        // access, but the key is gone right after.
+        let value = self.0.get(key);
+        self.0.invalidate(key);
+        value
+    }
+
+    fn clear(&mut self) {
+        self.0.invalidate_all();
+    }
+}
+
+impl<Key, Value> ShareableCache<Key, Value> for MiniMokaCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    fn get(&self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&self, key: Key, value: Value) {
+        self.0.insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::minimoka::MiniMokaCache;
+
+    cache_testkit::size_limited_cache_tests!(MiniMokaCache::with_capacity);
+    cache_testkit::removable_cache_tests!(MiniMokaCache::with_capacity);
+    cache_testkit::shareable_cache_tests!(MiniMokaCache::with_capacity);
+}
@@ -0,0 +1,496 @@
+//! Miss ratio curves: how a cache's miss ratio falls as its capacity grows.
+//!
+//! Replaying a whole trace once per capacity is slow when the trace has billions of requests.
+//! Two techniques make it fast:
+//!
+//! * Mattson's stack algorithm computes LRU's whole curve in one pass. An LRU cache of capacity
+//!   `c` hits exactly the requests whose stack distance, the number of distinct keys requested
+//!   since the last request for the same key, is at most `c`.
+//! * SHARDS ("Efficient MRC Construction with SHARDS", Waldspurger et al., FAST '15) samples keys
+//!   by hash, so a sampled key keeps all of its requests. A trace sampled at rate `R` behaves like
+//!   the full trace shrunk by `R`: its stack distances scale by `1 / R`, and running it through a
+//!   cache of capacity `R * c` estimates the full trace's miss ratio at `c`, for any policy.
+//!
+//! A few very popular keys can make a sample much busier, or much quieter, than `R` times the
+//! trace. Like SHARDS-adj, the estimates count misses relative to the number of requests a sample
+//! should have, rather than the number it happened to get.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    io,
+};
+
+use serde::Serialize;
+
+use crate::{
+    policy::Policy,
+    simulator::Simulator,
+    trace::{Op, Request},
+    workload::split_mix,
+};
+
+/// Sampling thresholds are out of this many hash values.
+const MODULUS: u64 = 1 << 24;
+
+/// The name [lru_mrc]'s curves are reported under.
+pub const STACK_DISTANCE_LRU: &str = "lru-stack";
+
+/// One point on a miss ratio curve.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MrcPoint {
+    pub policy: &'static str,
+    pub capacity: usize,
+    pub miss_ratio: f64,
+}
+
+impl MrcPoint {
+    pub const CSV_HEADER: &'static str = "policy,capacity,miss_ratio";
+
+    pub fn to_csv_row(&self) -> String {
+        format!("{},{},{:.6}", self.policy, self.capacity, self.miss_ratio)
+    }
+}
+
+/// Which keys' requests a curve is built from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Every request, for an exact curve.
+    Exact,
+    /// Each key is sampled with this probability, which must be in `(0, 1]`.
+    FixedRate(f64),
+    /// The sampling rate starts at 1 and is lowered whenever more than this many distinct keys
+    /// are sampled, so [lru_mrc] tracks at most this many keys however many the trace has, and
+    /// [SampledSimulator] keeps only those keys' requests.
+    FixedSize(usize),
+}
+
+/// LRU's miss ratio at each capacity, from one pass over the trace. Deletes are skipped, like in
+/// the [Simulator]. Stops at the first error reading the trace.
+///
+/// The trace is read one request at a time. It needs memory for the keys it tracks and for the
+/// capacities, but not for each request.
+pub fn lru_mrc(
+    requests: impl IntoIterator<Item = io::Result<Request>>,
+    sampling: Sampling,
+    capacities: &[usize],
+) -> io::Result<Vec<MrcPoint>> {
+    let mut stack = StackDistances::new();
+    let (mut threshold, mut smallest_hashes) = sampling_threshold(sampling);
+
+    // A histogram of the sampled requests' stack distances, scaled up to the full trace, over the
+    // capacities from smallest to largest: each request's weight goes to the smallest capacity
+    // that it hits at. A request sampled at rate R stands for 1 / R requests, so requests sampled
+    // before the rate was lowered count for less than the ones after.
+    let mut by_capacity: Vec<usize> = (0..capacities.len()).collect();
+    by_capacity.sort_by_key(|&index| capacities[index]);
+    let mut first_hits = vec![0.0; capacities.len()];
+    let mut total_weight = 0.0;
+    let mut total_requests = 0_u64;
+    for request in requests {
+        let request = request?;
+        if request.op == Op::Delete {
+            continue;
+        }
+        total_requests += 1;
+        let hash = sample_hash(request.key);
+        if threshold <= hash {
+            continue;
+        }
+        let rate = threshold as f64 / MODULUS as f64;
+        let distance = stack
+            .access(request.key)
+            .map_or(f64::INFINITY, |distance| distance as f64 / rate);
+        let weight = 1.0 / rate;
+        total_weight += weight;
+        let smallest_hit =
+            by_capacity.partition_point(|&index| (capacities[index] as f64) < distance);
+        if let Some(hits) = first_hits.get_mut(smallest_hit) {
+            *hits += weight;
+        }
+
+        if let Some(smallest_hashes) = &mut smallest_hashes {
+            smallest_hashes.insert(hash, request.key, |key| stack.forget(key));
+            threshold = smallest_hashes.threshold;
+        }
+    }
+
+    // A request that hits at one capacity hits at every bigger one too.
+    let mut hits = vec![0.0; capacities.len()];
+    let mut cumulative_hits = 0.0;
+    for (&index, first_hits) in by_capacity.iter().zip(&first_hits) {
+        cumulative_hits += first_hits;
+        hits[index] = cumulative_hits;
+    }
+
+    Ok(capacities
+        .iter()
+        .zip(hits)
+        .map(|(&capacity, hits)| MrcPoint {
+            policy: STACK_DISTANCE_LRU,
+            capacity,
+            miss_ratio: adjusted_miss_ratio(total_weight - hits, total_requests),
+        })
+        .collect())
+}
+
+/// Estimates miss ratio curves for any policy, by simulating a SHARDS-sampled trace at
+/// capacities scaled down by the sampling rate.
+///
+/// Small scaled capacities are noisy: sample enough keys that the smallest interesting capacity
+/// still holds a few hundred items after scaling.
+///
+/// The sampled requests are kept to replay at each capacity, but the rest of the trace isn't.
+pub struct SampledSimulator {
+    simulator: Simulator,
+    rate: f64,
+    /// How many requests the whole trace has, deletes aside.
+    total_requests: u64,
+}
+
+impl SampledSimulator {
+    /// Samples the trace in one pass. Deletes are skipped, and it stops at the first error
+    /// reading the trace.
+    pub fn new(
+        requests: impl IntoIterator<Item = io::Result<Request>>,
+        sampling: Sampling,
+    ) -> io::Result<Self> {
+        let (mut threshold, mut smallest_hashes) = sampling_threshold(sampling);
+        let mut sampled = Vec::new();
+        // With a fixed sample size, the threshold drops as keys are sampled, leaving requests for
+        // keys that aren't sampled any more. They're dropped whenever the sample has doubled since
+        // it was last pruned, so pruning costs a constant amount per request.
+        let mut pruned_len = 0;
+        let mut total_requests = 0_u64;
+        for request in requests {
+            let request = request?;
+            if request.op == Op::Delete {
+                continue;
+            }
+            total_requests += 1;
+            let hash = sample_hash(request.key);
+            if threshold <= hash {
+                continue;
+            }
+            sampled.push(request);
+            if let Some(smallest_hashes) = &mut smallest_hashes {
+                smallest_hashes.insert(hash, request.key, |_| {});
+                threshold = smallest_hashes.threshold;
+                if 2 * pruned_len.max(StackDistances::INITIAL_TIMES) <= sampled.len() {
+                    sampled.retain(|request| sample_hash(request.key) < threshold);
+                    pruned_len = sampled.len();
+                }
+            }
+        }
+        sampled.retain(|request| sample_hash(request.key) < threshold);
+        Ok(Self {
+            simulator: Simulator::new(sampled),
+            rate: threshold as f64 / MODULUS as f64,
+            total_requests,
+        })
+    }
+
+    /// The share of keys that were sampled.
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    pub fn mrc(&self, policy: Policy, capacities: &[usize]) -> Vec<MrcPoint> {
+        capacities
+            .iter()
+            .map(|&capacity| {
+                let scaled_capacity = ((capacity as f64 * self.rate).round() as usize).max(1);
+                let result = self.simulator.run(policy, scaled_capacity);
+                let misses = (result.requests - result.hits) as f64 / self.rate;
+                MrcPoint {
+                    policy: policy.name(),
+                    capacity,
+                    miss_ratio: adjusted_miss_ratio(misses, self.total_requests),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Scaled-up sampled misses over the trace's real request count, rather than over the scaled-up
+/// sampled request count.
+fn adjusted_miss_ratio(misses: f64, total_requests: u64) -> f64 {
+    if total_requests == 0 {
+        0.0
+    } else {
+        (misses / total_requests as f64).clamp(0.0, 1.0)
+    }
+}
+
+/// The threshold a sampling starts at, and for a fixed sample size, the smallest hashes that
+/// lower it.
+fn sampling_threshold(sampling: Sampling) -> (u64, Option<SmallestHashes>) {
+    match sampling {
+        Sampling::Exact => (MODULUS, None),
+        Sampling::FixedRate(rate) => (rate_threshold(rate), None),
+        Sampling::FixedSize(limit) => (MODULUS, Some(SmallestHashes::new(limit))),
+    }
+}
+
+fn sample_hash(key: u64) -> u64 {
+    split_mix(key) % MODULUS
+}
+
+fn rate_threshold(rate: f64) -> u64 {
+    assert!(
+        0.0 < rate && rate <= 1.0,
+        "sampling rate must be in (0, 1], not {rate}"
+    );
+    ((rate * MODULUS as f64).round() as u64).max(1)
+}
+
+/// Mattson's LRU stack, kept as a Fenwick tree over request times that marks the time of each
+/// key's latest request. A key's stack distance is the number of marks after its previous
+/// request, plus one for itself.
+///
+/// Only the marked times matter, so when the tree runs out of times it renumbers them from 0 and
+/// drops the rest. The tree stays a few times bigger than the number of keys in the stack.
+struct StackDistances {
+    latest: HashMap<u64, usize>,
+    marks: Vec<u32>,
+    marked: usize,
+    now: usize,
+}
+
+impl StackDistances {
+    /// How many times a new tree has room for.
+    const INITIAL_TIMES: usize = 1024;
+
+    fn new() -> Self {
+        Self {
+            latest: HashMap::new(),
+            marks: vec![0; Self::INITIAL_TIMES + 1],
+            marked: 0,
+            now: 0,
+        }
+    }
+
+    /// Records a request, returning its stack distance, or None if the key is new.
+    fn access(&mut self, key: u64) -> Option<usize> {
+        if self.marks.len() <= self.now + 1 {
+            self.compact();
+        }
+        let now = self.now;
+        self.now += 1;
+        let distance = self.latest.insert(key, now).map(|previous| {
+            let distance = self.marked - self.marked_through(previous) + 1;
+            self.unmark(previous);
+            distance
+        });
+        self.mark(now);
+        distance
+    }
+
+    /// Takes a key out of the stack, as if it had never been requested.
+    fn forget(&mut self, key: u64) {
+        if let Some(previous) = self.latest.remove(&key) {
+            self.unmark(previous);
+        }
+    }
+
+    /// Renumbers the keys' latest request times from 0, in order, into a tree with room for as
+    /// many new requests as there are keys.
+    fn compact(&mut self) {
+        let mut by_time: Vec<(usize, u64)> = self
+            .latest
+            .iter()
+            .map(|(&key, &time)| (time, key))
+            .collect();
+        by_time.sort_unstable();
+        let keys = by_time.len();
+        self.marks = vec![0; 2 * keys.max(Self::INITIAL_TIMES / 2) + 1];
+        self.marked = 0;
+        for (time, (_, key)) in by_time.into_iter().enumerate() {
+            self.latest.insert(key, time);
+            self.mark(time);
+        }
+        self.now = keys;
+    }
+
+    fn mark(&mut self, time: usize) {
+        self.marked += 1;
+        let mut index = time + 1;
+        while index < self.marks.len() {
+            self.marks[index] += 1;
+            index += index & index.wrapping_neg();
+        }
+    }
+
+    fn unmark(&mut self, time: usize) {
+        self.marked -= 1;
+        let mut index = time + 1;
+        while index < self.marks.len() {
+            self.marks[index] -= 1;
+            index += index & index.wrapping_neg();
+        }
+    }
+
+    /// How many marks there are at or before `time`.
+    fn marked_through(&self, time: usize) -> usize {
+        let mut count = 0;
+        let mut index = time + 1;
+        while 0 < index {
+            count += self.marks[index] as usize;
+            index &= index - 1;
+        }
+        count
+    }
+}
+
+/// Fixed-size SHARDS: the `limit` distinct sampled keys with the smallest hashes.
+struct SmallestHashes {
+    keys: BTreeSet<(u64, u64)>,
+    limit: usize,
+    /// Keys whose hash is below this are sampled.
+    threshold: u64,
+}
+
+impl SmallestHashes {
+    fn new(limit: usize) -> Self {
+        assert!(0 < limit, "sample size must be at least 1");
+        Self {
+            keys: BTreeSet::new(),
+            limit,
+            threshold: MODULUS,
+        }
+    }
+
+    /// Adds a sampled key. When that's one key too many, the threshold drops to the largest
+    /// sampled hash, and every key with that hash is passed to `forget`.
+    fn insert(&mut self, hash: u64, key: u64, mut forget: impl FnMut(u64)) {
+        self.keys.insert((hash, key));
+        while self.limit < self.keys.len() {
+            let (largest, _) = *self.keys.last().expect("over the limit, so not empty");
+            while let Some(&(hash, key)) = self.keys.last() {
+                if hash != largest {
+                    break;
+                }
+                self.keys.pop_last();
+                forget(key);
+            }
+            self.threshold = largest;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use crate::{
+        mrc::{lru_mrc, SampledSimulator, Sampling, StackDistances},
+        policy::Policy,
+        simulator::Simulator,
+        trace::{Op, Request},
+        workload::{KeyDistribution, WorkloadSpec},
+    };
+
+    fn trace(keys: &[u64]) -> Vec<Request> {
+        keys.iter()
+            .enumerate()
+            .map(|(timestamp, &key)| Request {
+                key,
+                size: 1,
+                op: Op::Get,
+                timestamp: timestamp as u64,
+            })
+            .collect()
+    }
+
+    /// Reads requests the way [lru_mrc] and [SampledSimulator] take them from a trace.
+    fn stream(requests: &[Request]) -> impl Iterator<Item = io::Result<Request>> + '_ {
+        requests.iter().copied().map(Ok)
+    }
+
+    fn miss_ratios(points: &[crate::mrc::MrcPoint]) -> Vec<f64> {
+        points.iter().map(|point| point.miss_ratio).collect()
+    }
+
+    fn zipf_trace() -> Vec<Request> {
+        WorkloadSpec::new(KeyDistribution::Zipf {
+            key_space: 100_000,
+            alpha: 0.8,
+        })
+        .generate()
+        .take(300_000)
+        .collect()
+    }
+
+    #[test]
+    fn textbook_lru_faults() {
+        // The classic reference string: LRU takes 10 faults with 3 frames and 8 with 4.
+        let requests = trace(&[1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5]);
+        let curve = lru_mrc(stream(&requests), Sampling::Exact, &[1, 3, 4, 5]).expect("no errors");
+        assert_eq!(
+            miss_ratios(&curve),
+            vec![1.0, 10.0 / 12.0, 8.0 / 12.0, 5.0 / 12.0]
+        );
+    }
+
+    #[test]
+    fn the_stack_stays_sized_by_its_keys() {
+        let mut stack = StackDistances::new();
+        for round in 0..100 {
+            for key in 0..50 {
+                let distance = stack.access(key);
+                assert_eq!(
+                    distance,
+                    (0 < round).then_some(50),
+                    "round {round}, key {key}"
+                );
+            }
+        }
+        assert_eq!(stack.marks.len(), StackDistances::INITIAL_TIMES + 1);
+    }
+
+    #[test]
+    fn sampled_lru_is_close_to_exact() {
+        let requests = zipf_trace();
+        let capacities = [1_000, 5_000, 20_000];
+        let curve =
+            |sampling| lru_mrc(stream(&requests), sampling, &capacities).expect("no errors");
+        let exact = miss_ratios(&curve(Sampling::Exact));
+        for sampling in [Sampling::FixedRate(0.1), Sampling::FixedSize(5_000)] {
+            let sampled = miss_ratios(&curve(sampling));
+            for (exact, sampled) in exact.iter().zip(&sampled) {
+                assert!(
+                    (exact - sampled).abs() < 0.03,
+                    "{sampling:?}: {exact} vs {sampled}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sampled_simulation_is_close_to_full() {
+        let requests = zipf_trace();
+        let full = Simulator::new(requests.clone());
+        let sampled = SampledSimulator::new(stream(&requests), Sampling::FixedSize(5_000))
+            .expect("no errors");
+        assert!(sampled.rate() < 0.1, "rate {}", sampled.rate());
+
+        for point in sampled.mrc(Policy::Example, &[5_000, 20_000]) {
+            let exact = 1.0 - full.run(Policy::Example, point.capacity).hit_ratio;
+            assert!(
+                (exact - point.miss_ratio).abs() < 0.03,
+                "{exact} vs {}",
+                point.miss_ratio
+            );
+        }
+    }
+
+    #[test]
+    fn trace_errors_are_returned() {
+        let requests = trace(&[1, 2, 3]);
+        let broken =
+            || stream(&requests).chain([Err(io::Error::new(io::ErrorKind::InvalidData, "line 4"))]);
+        for sampling in [Sampling::Exact, Sampling::FixedSize(2)] {
+            lru_mrc(broken(), sampling, &[1]).expect_err("the trace is broken");
+            assert!(SampledSimulator::new(broken(), sampling).is_err());
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! Self-contained SVG line charts, for the graphs that go in design docs.
+//!
+//! There's no plotting dependency and no gnuplot needed: a chart is a handful of SVG elements.
+//! Every series gets its own color, dash pattern and marker shape, so the lines can be told apart
+//! without relying on color vision.
+
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const WIDTH: f64 = 860.0;
+const HEIGHT: f64 = 500.0;
+const LEFT: f64 = 80.0;
+const RIGHT: f64 = 190.0;
+const TOP: f64 = 50.0;
+const BOTTOM: f64 = 60.0;
+
+/// The Okabe-Ito palette, which stays distinguishable for the common kinds of color blindness.
+const COLORS: [&str; 8] = [
+    "#000000", "#E69F00", "#56B4E9", "#009E73", "#D55E00", "#0072B2", "#CC79A7", "#F0E442",
+];
+const DASHES: [&str; 4] = ["none", "8 4", "2 3", "10 3 2 3"];
+
+/// One line on a chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineChart {
+    pub title: String,
+    pub x_label: String,
+    pub y_label: String,
+    /// Use a logarithmic x axis, for capacities that span orders of magnitude.
+    pub log_x: bool,
+    pub series: Vec<Series>,
+}
+
+impl LineChart {
+    pub fn new(title: &str, x_label: &str, y_label: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            x_label: x_label.to_string(),
+            y_label: y_label.to_string(),
+            log_x: false,
+            series: Vec::new(),
+        }
+    }
+
+    /// Adds a point to the named series, creating the series if it's new.
+    pub fn add_point(&mut self, series: &str, x: f64, y: f64) {
+        match self
+            .series
+            .iter_mut()
+            .find(|existing| existing.name == series)
+        {
+            Some(existing) => existing.points.push((x, y)),
+            None => self.series.push(Series {
+                name: series.to_string(),
+                points: vec![(x, y)],
+            }),
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let points = || self.series.iter().flat_map(|series| series.points.iter());
+        let (mut x_min, mut x_max) = bounds(points().map(|&(x, _)| x));
+        let (_, y_max) = bounds(points().map(|&(_, y)| y));
+        let log_x = self.log_x && 0.0 < x_min;
+        if log_x {
+            (x_min, x_max) = (x_min.log10(), x_max.log10());
+        }
+        if x_min == x_max {
+            (x_min, x_max) = (x_min - 1.0, x_max + 1.0);
+        }
+        let y_ticks = nice_ticks(0.0, y_max.max(f64::MIN_POSITIVE));
+        let y_max = y_ticks[y_ticks.len() - 1];
+
+        let plot_width = WIDTH - LEFT - RIGHT;
+        let plot_height = HEIGHT - TOP - BOTTOM;
+        let x_position = |x: f64| {
+            let x = if log_x { x.log10() } else { x };
+            LEFT + (x - x_min) / (x_max - x_min) * plot_width
+        };
+        let y_position = |y: f64| TOP + plot_height - y / y_max * plot_height;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="sans-serif" font-size="13">"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{WIDTH}" height="{HEIGHT}" fill="white"/>"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="28" text-anchor="middle" font-size="17">{}</text>"#,
+            LEFT + plot_width / 2.0,
+            escape(&self.title)
+        );
+
+        // Grid lines and tick labels.
+        for &y in &y_ticks {
+            let position = y_position(y);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{LEFT}" y1="{position:.1}" x2="{:.1}" y2="{position:.1}" stroke="#dddddd"/>"##,
+                LEFT + plot_width
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"#,
+                LEFT - 8.0,
+                position + 4.0,
+                format_number(y)
+            );
+        }
+        let x_ticks = if log_x {
+            (x_min.floor() as i32..=x_max.ceil() as i32)
+                .map(|exponent| 10_f64.powi(exponent))
+                .filter(|&x| x_min <= x.log10() && x.log10() <= x_max)
+                .collect()
+        } else {
+            nice_ticks(x_min, x_max)
+                .into_iter()
+                .filter(|&x| x_min <= x && x <= x_max)
+                .collect::<Vec<_>>()
+        };
+        for x in x_ticks {
+            let position = x_position(x);
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{position:.1}" y1="{TOP}" x2="{position:.1}" y2="{:.1}" stroke="#dddddd"/>"##,
+                TOP + plot_height
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{position:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                TOP + plot_height + 20.0,
+                format_number(x)
+            );
+        }
+
+        // Axes and their labels.
+        let _ = writeln!(
+            svg,
+            r#"<path d="M{LEFT} {TOP} V{:.1} H{:.1}" fill="none" stroke="black"/>"#,
+            TOP + plot_height,
+            LEFT + plot_width
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            LEFT + plot_width / 2.0,
+            HEIGHT - 15.0,
+            escape(&self.x_label)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text transform="translate(20 {:.1}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            TOP + plot_height / 2.0,
+            escape(&self.y_label)
+        );
+
+        // The series, and a legend entry for each.
+        for (index, series) in self.series.iter().enumerate() {
+            let color = COLORS[index % COLORS.len()];
+            let dash = DASHES[index % DASHES.len()];
+            let mut points = series.points.clone();
+            points.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let coordinates: Vec<String> = points
+                .iter()
+                .map(|&(x, y)| format!("{:.1},{:.1}", x_position(x), y_position(y)))
+                .collect();
+            let _ = writeln!(
+                svg,
+                r#"<polyline points="{}" fill="none" stroke="{color}" stroke-width="2" stroke-dasharray="{dash}"/>"#,
+                coordinates.join(" ")
+            );
+            for &(x, y) in &points {
+                svg.push_str(&marker(index, x_position(x), y_position(y), color));
+            }
+
+            let legend_x = WIDTH - RIGHT + 20.0;
+            let legend_y = TOP + 10.0 + 22.0 * index as f64;
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{legend_x}" y1="{legend_y}" x2="{}" y2="{legend_y}" stroke="{color}" stroke-width="2" stroke-dasharray="{dash}"/>"#,
+                legend_x + 36.0
+            );
+            svg.push_str(&marker(index, legend_x + 18.0, legend_y, color));
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                legend_x + 44.0,
+                legend_y + 4.0,
+                escape(&series.name)
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn write_svg(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+/// Plots miss ratio against capacity, one line per policy, on a log scale.
+pub fn miss_ratio_chart(
+    title: &str,
+    points: impl IntoIterator<Item = (&'static str, usize, f64)>,
+) -> LineChart {
+    let mut chart = LineChart::new(title, "capacity (items)", "miss ratio");
+    chart.log_x = true;
+    for (policy, capacity, miss_ratio) in points {
+        chart.add_point(policy, capacity as f64, miss_ratio);
+    }
+    chart
+}
+
+/// Plots throughput against thread count from criterion's saved results for a benchmark group
+/// whose benchmarks are named `implementation/thread count`, like `multi_thread`.
+///
+/// Each iteration of those benchmarks is one cache operation on some thread, so the mean time
+/// per iteration is the inverse of the combined throughput of all threads.
+pub fn throughput_chart(criterion_group_directory: &Path) -> io::Result<LineChart> {
+    if !criterion_group_directory.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no benchmark results at {criterion_group_directory:?}"),
+        ));
+    }
+    let mut chart = LineChart::new(
+        "Throughput by thread count",
+        "threads",
+        "million operations per second",
+    );
+    for benchmark in benchmark_directories(criterion_group_directory)? {
+        let read_json = |name: &str| -> io::Result<serde_json::Value> {
+            let text = fs::read_to_string(benchmark.join("new").join(name))?;
+            serde_json::from_str(&text).map_err(io::Error::other)
+        };
+        let description = read_json("benchmark.json")?;
+        let estimates = read_json("estimates.json")?;
+        let implementation = description["function_id"].as_str();
+        let threads = description["value_str"]
+            .as_str()
+            .and_then(|threads| threads.parse::<f64>().ok());
+        let nanoseconds = estimates["mean"]["point_estimate"].as_f64();
+        match (implementation, threads, nanoseconds) {
+            (Some(implementation), Some(threads), Some(nanoseconds)) if 0.0 < nanoseconds => {
+                chart.add_point(implementation, threads, 1_000.0 / nanoseconds);
+            }
+            _ => log::warn!("skipping {benchmark:?}, which isn't named implementation/threads"),
+        }
+    }
+    if chart.series.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no benchmark results under {criterion_group_directory:?}"),
+        ));
+    }
+    chart.series.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(chart)
+}
+
+/// Every directory under `directory` that holds a criterion result in `new/benchmark.json`.
+fn benchmark_directories(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if !path.is_dir() || path.file_name().is_some_and(|name| name == "report") {
+            continue;
+        }
+        if path.join("new").join("benchmark.json").is_file() {
+            found.push(path);
+        } else {
+            found.extend(benchmark_directories(&path)?);
+        }
+    }
+    Ok(found)
+}
+
+fn marker(index: usize, x: f64, y: f64, color: &str) -> String {
+    let mut marker = match index % 5 {
+        0 => format!(r#"<circle cx="{x:.1}" cy="{y:.1}" r="4" fill="{color}"/>"#),
+        1 => format!(
+            r#"<rect x="{:.1}" y="{:.1}" width="8" height="8" fill="{color}"/>"#,
+            x - 4.0,
+            y - 4.0
+        ),
+        2 => format!(
+            r#"<path d="M{x:.1} {:.1} L{:.1} {:.1} L{:.1} {:.1} Z" fill="{color}"/>"#,
+            y - 5.0,
+            x + 5.0,
+            y + 4.0,
+            x - 5.0,
+            y + 4.0
+        ),
+        3 => format!(
+            r#"<path d="M{x:.1} {:.1} L{:.1} {y:.1} L{x:.1} {:.1} L{:.1} {y:.1} Z" fill="{color}"/>"#,
+            y - 5.0,
+            x + 5.0,
+            y + 5.0,
+            x - 5.0
+        ),
+        _ => format!(
+            r#"<path d="M{:.1} {:.1} L{:.1} {:.1} M{:.1} {:.1} L{:.1} {:.1}" stroke="{color}" stroke-width="2"/>"#,
+            x - 4.0,
+            y - 4.0,
+            x + 4.0,
+            y + 4.0,
+            x - 4.0,
+            y + 4.0,
+            x + 4.0,
+            y - 4.0
+        ),
+    };
+    marker.push('\n');
+    marker
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+        (min.min(value), max.max(value))
+    })
+}
+
+/// Round-numbered ticks, 1, 2 or 5 times a power of ten apart, covering `min..=max`.
+fn nice_ticks(min: f64, max: f64) -> Vec<f64> {
+    if !min.is_finite() || !max.is_finite() || max <= min {
+        return vec![0.0, 1.0];
+    }
+    let rough_step = (max - min) / 5.0;
+    let magnitude = 10_f64.powf(rough_step.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|multiple| multiple * magnitude)
+        .find(|&step| rough_step <= step)
+        .unwrap_or(10.0 * magnitude);
+    let first = (min / step).floor() as i64;
+    let last = (max / step).ceil() as i64;
+    (first..=last).map(|tick| tick as f64 * step).collect()
+}
+
+fn format_number(value: f64) -> String {
+    if 1_000_000.0 <= value.abs() {
+        format!("{value:.0e}")
+    } else {
+        // Round away floating point noise like 0.30000000000000004.
+        format!("{}", (value * 1e6).round() / 1e6)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::plot::{format_number, miss_ratio_chart, nice_ticks, throughput_chart};
+
+    #[test]
+    fn miss_ratio_chart_svg() {
+        let chart = miss_ratio_chart(
+            "a <trace>",
+            [
+                ("lru", 10, 0.9),
+                ("lru", 1000, 0.5),
+                ("lirs", 10, 0.8),
+                ("lirs", 1000, 0.4),
+            ],
+        );
+        assert_eq!(chart.series.len(), 2);
+        let svg = chart.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains("a &lt;trace&gt;"));
+        assert!(svg.contains(">lirs</text>"));
+    }
+
+    #[test]
+    fn ticks_are_round() {
+        let labels = |min, max| -> Vec<String> {
+            nice_ticks(min, max)
+                .into_iter()
+                .map(format_number)
+                .collect()
+        };
+        assert_eq!(labels(0.0, 1.0), ["0", "0.2", "0.4", "0.6", "0.8", "1"]);
+        assert_eq!(labels(0.0, 17.0), ["0", "5", "10", "15", "20"]);
+        assert_eq!(labels(1.0, 16.0), ["0", "5", "10", "15", "20"]);
+    }
+
+    #[test]
+    fn throughput_from_criterion_results() {
+        let group = std::env::temp_dir().join(format!("plot-test-{}", std::process::id()));
+        for (implementation, threads, nanoseconds) in [("lru", 1, 100.0), ("lru", 4, 50.0)] {
+            let directory = group
+                .join(implementation)
+                .join(threads.to_string())
+                .join("new");
+            fs::create_dir_all(&directory).expect("temp dir is writable");
+            fs::write(
+                directory.join("benchmark.json"),
+                format!(r#"{{"function_id":"{implementation}","value_str":"{threads}"}}"#),
+            )
+            .expect("temp dir is writable");
+            fs::write(
+                directory.join("estimates.json"),
+                format!(r#"{{"mean":{{"point_estimate":{nanoseconds}}}}}"#),
+            )
+            .expect("temp dir is writable");
+        }
+
+        let chart = throughput_chart(&group).expect("results are readable");
+        let _ = fs::remove_dir_all(&group);
+        let mut points = chart.series[0].points.clone();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(chart.series[0].name, "lru");
+        assert_eq!(points, vec![(1.0, 10.0), (4.0, 20.0)]);
+    }
+}
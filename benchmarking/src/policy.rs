@@ -0,0 +1,226 @@
+use std::{hash::Hash, sync::Arc};
+
+use cache::{synchronized_cache, ShareableCache, SizeLimitedCache};
+use cache_policies::{BeladyCache, GdsfCache, LirsCache, SampleScoring, SampledCache, TwoQCache};
+use example_sieve_cache::{ClockCache, SurvivorPolicy};
+
+use crate::{
+    hashlink_lru::HashlinkLruCache,
+    kcache::{KCache, SharableKCache},
+    lru_cache::LruCache,
+    lru_crate::LruCrateCache,
+    minimoka::MiniMokaCache,
+    quickcache::QuickCache,
+};
+
+/// Every cache implementation the simulator knows how to build.
+///
+/// This is an enum rather than a list of `Box<dyn SizeLimitedCache>` constructors. See the intro's
+/// notes on dynamic dispatch: [Policy::build] matches on the policy once, and then whatever uses
+/// the cache is compiled separately for each concrete cache type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Policy {
+    /// Your sieve cache, from the `sieve_cache` crate.
+    Workshop,
+    /// The example sieve cache.
+    Example,
+    /// The generalized cache configured as 1-bit SIEVE.
+    Sieve,
+    /// The generalized cache configured as 1-bit CLOCK.
+    Clock,
+    /// The generalized cache configured as 2-bit CLOCK.
+    #[value(name = "clock-2bit")]
+    Clock2Bit,
+    #[value(name = "2q")]
+    TwoQ,
+    Lirs,
+    Gdsf,
+    /// Sampled eviction of a random entry.
+    Random,
+    /// Sampled eviction approximating LRU.
+    SampledLru,
+    /// Sampled eviction approximating LFU.
+    SampledLfu,
+    /// The k-cache crate's sieve cache.
+    #[value(name = "k-cache")]
+    KCache,
+    /// The moka crate's cache.
+    Lru,
+    /// The lru crate's cache.
+    #[value(name = "lru-crate")]
+    LruCrate,
+    /// The quick_cache crate's cache.
+    QuickCache,
+    /// The mini-moka crate's cache.
+    MiniMoka,
+    /// An LRU cache on the hashlink crate's linked hash map.
+    Hashlink,
+    /// Belady's MIN, the offline optimal policy.
+    Belady,
+}
+
+impl Policy {
+    pub const ALL: [Policy; 18] = [
+        Policy::Workshop,
+        Policy::Example,
+        Policy::Sieve,
+        Policy::Clock,
+        Policy::Clock2Bit,
+        Policy::TwoQ,
+        Policy::Lirs,
+        Policy::Gdsf,
+        Policy::Random,
+        Policy::SampledLru,
+        Policy::SampledLfu,
+        Policy::KCache,
+        Policy::Lru,
+        Policy::LruCrate,
+        Policy::QuickCache,
+        Policy::MiniMoka,
+        Policy::Hashlink,
+        Policy::Belady,
+    ];
+
+    /// The name used on the command line and in reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Policy::Workshop => "workshop",
+            Policy::Example => "example",
+            Policy::Sieve => "sieve",
+            Policy::Clock => "clock",
+            Policy::Clock2Bit => "clock-2bit",
+            Policy::TwoQ => "2q",
+            Policy::Lirs => "lirs",
+            Policy::Gdsf => "gdsf",
+            Policy::Random => "random",
+            Policy::SampledLru => "sampled-lru",
+            Policy::SampledLfu => "sampled-lfu",
+            Policy::KCache => "k-cache",
+            Policy::Lru => "lru",
+            Policy::LruCrate => "lru-crate",
+            Policy::QuickCache => "quick-cache",
+            Policy::MiniMoka => "mini-moka",
+            Policy::Hashlink => "hashlink",
+            Policy::Belady => "belady",
+        }
+    }
+
+    /// Builds a cache of this policy and capacity, and hands it to `visitor`.
+    ///
+    /// Belady's MIN needs to know the future, so `next_uses` gives the next use of each request
+    /// the cache will see (see [cache_policies::next_use_indices]). It's only called for Belady.
+    pub fn build<Key, Value, Visitor>(
+        self,
+        capacity: usize,
+        next_uses: impl FnOnce() -> Arc<[usize]>,
+        visitor: Visitor,
+    ) -> Visitor::Output
+    where
+        Key: Eq + Hash + Clone + Send + Sync + 'static,
+        Value: Clone + Send + Sync + 'static,
+        Visitor: CacheVisitor<Key, Value>,
+    {
+        match self {
+            Policy::Workshop => visitor.visit(sieve_cache::SieveCache::with_capacity(capacity)),
+            Policy::Example => {
+                visitor.visit(example_sieve_cache::SieveCache::with_capacity(capacity))
+            }
+            Policy::Sieve => visitor.visit(ClockCache::sieve(capacity)),
+            Policy::Clock => visitor.visit(ClockCache::clock(capacity)),
+            Policy::Clock2Bit => {
+                visitor.visit(ClockCache::new(capacity, 2, SurvivorPolicy::MoveToHead))
+            }
+            Policy::TwoQ => visitor.visit(TwoQCache::new(capacity)),
+            Policy::Lirs => visitor.visit(LirsCache::new(capacity)),
+            Policy::Gdsf => visitor.visit(GdsfCache::new(capacity)),
+            Policy::Random => visitor.visit(SampledCache::new(capacity, 1, SampleScoring::Random)),
+            Policy::SampledLru => visitor.visit(SampledCache::new(
+                capacity,
+                5,
+                SampleScoring::ApproximateLru,
+            )),
+            Policy::SampledLfu => visitor.visit(SampledCache::new(
+                capacity,
+                5,
+                SampleScoring::ApproximateLfu,
+            )),
+            Policy::KCache => visitor.visit(KCache::with_capacity(capacity)),
+            Policy::Lru => visitor.visit(LruCache::with_capacity(capacity)),
+            Policy::LruCrate => visitor.visit(LruCrateCache::with_capacity(capacity)),
+            Policy::QuickCache => visitor.visit(QuickCache::with_capacity(capacity)),
+            Policy::MiniMoka => visitor.visit(MiniMokaCache::with_capacity(capacity)),
+            Policy::Hashlink => visitor.visit(HashlinkLruCache::with_capacity(capacity)),
+            Policy::Belady => visitor.visit(BeladyCache::new(capacity, next_uses())),
+        }
+    }
+
+    /// Builds a cache of this policy and capacity that threads can share, and hands it to
+    /// `visitor`. The adapted crates' caches are shareable as they are; the rest are wrapped in a
+    /// mutex.
+    ///
+    /// Returns None for Belady, which can't serve live traffic: it needs the whole trace up front.
+    pub fn build_shared<Key, Value, Visitor>(
+        self,
+        capacity: usize,
+        visitor: Visitor,
+    ) -> Option<Visitor::Output>
+    where
+        Key: Eq + Hash + Clone + Send + Sync + 'static,
+        Value: Clone + Send + Sync + 'static,
+        Visitor: SharedCacheVisitor<Key, Value>,
+    {
+        match self {
+            Policy::KCache => Some(visitor.visit(SharableKCache::with_capacity(capacity))),
+            Policy::Lru => Some(visitor.visit(LruCache::with_capacity(capacity))),
+            Policy::LruCrate => Some(visitor.visit(LruCrateCache::with_capacity(capacity))),
+            Policy::QuickCache => Some(visitor.visit(QuickCache::with_capacity(capacity))),
+            Policy::MiniMoka => Some(visitor.visit(MiniMokaCache::with_capacity(capacity))),
+            Policy::Hashlink => Some(visitor.visit(HashlinkLruCache::with_capacity(capacity))),
+            Policy::Belady => None,
+            _ => Some(self.build(
+                capacity,
+                || unreachable!("only Belady needs next uses"),
+                Synchronized(visitor),
+            )),
+        }
+    }
+}
+
+/// Something to do with a cache of whichever policy [Policy::build] makes. This is a trait rather
+/// than a closure because a closure can't be generic over the type of cache it's given.
+pub trait CacheVisitor<Key, Value>
+where
+    Key: Eq + Hash,
+    Value: Clone,
+{
+    type Output;
+
+    fn visit(self, cache: impl SizeLimitedCache<Key, Value> + Send) -> Self::Output;
+}
+
+/// Something to do with a shareable cache of whichever policy [Policy::build_shared] makes.
+pub trait SharedCacheVisitor<Key, Value>
+where
+    Key: Eq + Hash,
+    Value: Clone,
+{
+    type Output;
+
+    fn visit(self, cache: impl ShareableCache<Key, Value>) -> Self::Output;
+}
+
+/// Wraps the cache in a mutex and passes it on.
+struct Synchronized<Visitor>(Visitor);
+
+impl<Key, Value, Visitor> CacheVisitor<Key, Value> for Synchronized<Visitor>
+where
+    Key: Eq + Hash,
+    Value: Clone,
+    Visitor: SharedCacheVisitor<Key, Value>,
+{
+    type Output = Visitor::Output;
+
+    fn visit(self, cache: impl SizeLimitedCache<Key, Value> + Send) -> Self::Output {
+        self.0.visit(synchronized_cache(cache))
+    }
+}
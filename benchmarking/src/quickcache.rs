@@ -0,0 +1,87 @@
+use cache::{RemovableCache, ShareableCache, SizeLimitedCache, MAX_SIZE};
+
+/// The quick_cache crate's concurrent cache, which evicts with CLOCK-Pro. Its capacity is a
+/// target rather than a hard limit.
+pub struct QuickCache<Key, Value>(quick_cache::sync::Cache<Key, Value>);
+
+impl<Key, Value> QuickCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    pub fn new() -> Self {
+        Self::with_capacity(MAX_SIZE)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(quick_cache::sync::Cache::new(capacity))
+    }
+}
+
+impl<Key, Value> Default for QuickCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for QuickCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0.insert(key, value);
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for QuickCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        self.0.remove(key).map(|(_, value)| value)
+    }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl<Key, Value> ShareableCache<Key, Value> for QuickCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Send + Sync,
+    Value: Clone + Send + Sync,
+{
+    fn get(&self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&self, key: Key, value: Value) {
+        self.0.insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache_testkit::{Capacity, Expectations};
+
+    use crate::quickcache::QuickCache;
+
+    const EXPECTATIONS: Expectations = Expectations {
+        capacity: Capacity::AtMost,
+        ..Expectations::STRICT
+    };
+
+    cache_testkit::size_limited_cache_tests!(QuickCache::with_capacity, EXPECTATIONS);
+    cache_testkit::removable_cache_tests!(QuickCache::with_capacity, EXPECTATIONS);
+    cache_testkit::shareable_cache_tests!(QuickCache::with_capacity, EXPECTATIONS);
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, OnceLock},
+};
+
+use cache::SizeLimitedCache;
+use cache_policies::next_use_indices;
+use serde::Serialize;
+
+use crate::{
+    policy::{CacheVisitor, Policy},
+    trace::{Op, Request},
+};
+
+/// The outcome of replaying a trace through one policy at one capacity.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationResult {
+    pub policy: &'static str,
+    pub capacity: usize,
+    pub requests: u64,
+    pub hits: u64,
+    pub hit_ratio: f64,
+    pub bytes_requested: u64,
+    pub bytes_hit: u64,
+    pub byte_hit_ratio: f64,
+    /// Insertions that aren't resident at the end of the trace: the evictions, plus any keys the
+    /// cache turned away instead of admitting. SizeLimitedCache can't report its length, so the
+    /// two can't be told apart.
+    pub insertions_not_resident: u64,
+}
+
+impl SimulationResult {
+    pub const CSV_HEADER: &'static str = "policy,capacity,requests,hits,hit_ratio,bytes_requested,bytes_hit,byte_hit_ratio,insertions_not_resident";
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{:.6},{},{},{:.6},{}",
+            self.policy,
+            self.capacity,
+            self.requests,
+            self.hits,
+            self.hit_ratio,
+            self.bytes_requested,
+            self.bytes_hit,
+            self.byte_hit_ratio,
+            self.insertions_not_resident
+        )
+    }
+}
+
+/// Replays a trace through caches as read-through: every request is a `get`, and every miss is
+/// followed by a `set` of the requested key. The cached value is the object's size.
+///
+/// Like libCacheSim, gets and sets are both treated as requests for the object. Deletes are
+/// skipped, because SizeLimitedCache has no way to remove a key.
+pub struct Simulator {
+    requests: Vec<Request>,
+    /// Only Belady needs these, and they take a pass over the trace, so they're computed on
+    /// first use and then shared by every capacity.
+    next_uses: OnceLock<Arc<[usize]>>,
+}
+
+impl Simulator {
+    pub fn new(mut requests: Vec<Request>) -> Self {
+        requests.retain(|request| request.op != Op::Delete);
+        Self {
+            requests,
+            next_uses: OnceLock::new(),
+        }
+    }
+
+    /// Runs the whole trace through a new cache of the given policy and capacity.
+    pub fn run(&self, policy: Policy, capacity: usize) -> SimulationResult {
+        let counts = policy.build(capacity, || self.next_uses(), Replay(&self.requests));
+
+        SimulationResult {
+            policy: policy.name(),
+            capacity,
+            requests: counts.requests,
+            hits: counts.hits,
+            hit_ratio: ratio(counts.hits, counts.requests),
+            bytes_requested: counts.bytes_requested,
+            bytes_hit: counts.bytes_hit,
+            byte_hit_ratio: ratio(counts.bytes_hit, counts.bytes_requested),
+            insertions_not_resident: counts.insertions_not_resident,
+        }
+    }
+
+    fn next_uses(&self) -> Arc<[usize]> {
+        self.next_uses
+            .get_or_init(|| {
+                next_use_indices(self.requests.iter().map(|request| request.key)).into()
+            })
+            .clone()
+    }
+}
+
+/// Replays requests as read-through, counting what happens.
+struct Replay<'a>(&'a [Request]);
+
+impl CacheVisitor<u64, u32> for Replay<'_> {
+    type Output = Counts;
+
+    fn visit(self, mut cache: impl SizeLimitedCache<u64, u32> + Send) -> Counts {
+        let mut counts = Counts::default();
+        let mut inserted = HashSet::new();
+        let mut insertions = 0_u64;
+
+        for request in self.0 {
+            counts.requests += 1;
+            counts.bytes_requested += u64::from(request.size);
+            if cache.get(&request.key).is_some() {
+                counts.hits += 1;
+                counts.bytes_hit += u64::from(request.size);
+            } else {
+                cache.set(request.key, request.size);
+                inserted.insert(request.key);
+                insertions += 1;
+            }
+        }
+
+        // SizeLimitedCache can't report evictions, or even its length, so look up every key that
+        // was inserted once the trace is done. These gets come after everything is counted, but
+        // they are still requests: Belady sees them as past the end of its trace, where no key is
+        // used again, and moka gets a chance to apply evictions it had pending.
+        let resident = inserted
+            .iter()
+            .filter(|key| cache.get(key).is_some())
+            .count() as u64;
+        counts.insertions_not_resident = insertions.saturating_sub(resident);
+        counts
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    requests: u64,
+    hits: u64,
+    bytes_requested: u64,
+    bytes_hit: u64,
+    insertions_not_resident: u64,
+}
+
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        policy::Policy,
+        simulator::Simulator,
+        trace::{Op, Request},
+    };
+
+    fn trace(keys: &[u64]) -> Vec<Request> {
+        keys.iter()
+            .enumerate()
+            .map(|(timestamp, &key)| Request {
+                key,
+                size: key as u32 * 10,
+                op: Op::Get,
+                timestamp: timestamp as u64,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn belady_textbook_reference_string() {
+        let simulator = Simulator::new(trace(&[1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5]));
+        let result = simulator.run(Policy::Belady, 3);
+        assert_eq!(result.requests, 12);
+        assert_eq!(result.hits, 5);
+        assert_eq!(result.insertions_not_resident, 4, "7 misses into 3 slots");
+        assert_eq!(result.bytes_requested, 330);
+        assert_eq!(result.bytes_hit, 10 + 20 + 10 + 20 + 50);
+    }
+
+    #[test]
+    fn every_policy_hits_a_trace_that_fits() {
+        let simulator = Simulator::new(trace(&[1, 2, 3, 1, 2, 3, 1, 2, 3]));
+        for policy in Policy::ALL {
+            if policy == Policy::Workshop {
+                // The workshop cache is yours to write!
+                continue;
+            }
+            let result = simulator.run(policy, 10);
+            assert_eq!(result.hits, 6, "{policy:?}");
+            assert_eq!(result.hit_ratio, 6.0 / 9.0, "{policy:?}");
+            assert_eq!(result.insertions_not_resident, 0, "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn deletes_are_skipped() {
+        let mut requests = trace(&[1, 1, 2]);
+        requests[1].op = Op::Delete;
+        requests[2].op = Op::Set;
+        let result = Simulator::new(requests).run(Policy::Example, 10);
+        assert_eq!(result.requests, 2);
+        assert_eq!(result.hits, 0);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{fs::File, io, iter::Take, path::PathBuf};
+
+use crate::{
+    trace::{open_trace, CsvColumns, Request, TraceFormat, TraceReader},
+    workload::{Workload, WorkloadArgs},
+};
+
+/// Command line options for where requests come from: a trace file, or a synthetic workload.
+#[derive(Debug, Clone, clap::Args)]
+pub struct RequestSource {
+    /// The trace file. It may be gzip or zstd compressed. Without a trace, a synthetic workload
+    /// described by the workload options is generated instead.
+    #[arg(long)]
+    pub trace: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = TraceFormat::Text)]
+    pub trace_format: TraceFormat,
+
+    #[command(flatten)]
+    pub csv_columns: CsvColumns,
+
+    #[command(flatten)]
+    pub workload: WorkloadArgs,
+}
+
+impl RequestSource {
+    /// Reads the whole trace, or generates the whole workload.
+    pub fn load(&self) -> io::Result<Vec<Request>> {
+        match &self.trace {
+            Some(trace) => {
+                let requests = open_trace(trace, self.trace_format, &self.csv_columns)?
+                    .collect::<io::Result<Vec<Request>>>()?;
+                log::info!("read {} requests from {trace:?}", requests.len());
+                Ok(requests)
+            }
+            None => {
+                let requests = self.workload.requests()?;
+                log::info!(
+                    "generated {} requests: {:?}",
+                    requests.len(),
+                    self.workload.spec()?
+                );
+                Ok(requests)
+            }
+        }
+    }
+
+    /// Reads the trace, or generates the workload, one request at a time, without holding the
+    /// whole of it. Each call starts again from the first request.
+    pub fn stream(&self) -> io::Result<RequestStream> {
+        match &self.trace {
+            Some(trace) => {
+                log::info!("reading requests from {trace:?}");
+                Ok(RequestStream::Trace(open_trace(
+                    trace,
+                    self.trace_format,
+                    &self.csv_columns,
+                )?))
+            }
+            None => {
+                let spec = self.workload.spec()?;
+                log::info!("generating {} requests: {spec:?}", self.workload.requests);
+                Ok(RequestStream::Workload(
+                    spec.generate().take(self.workload.requests),
+                ))
+            }
+        }
+    }
+}
+
+/// The requests of a [RequestSource], one at a time.
+pub enum RequestStream {
+    Trace(TraceReader<File>),
+    Workload(Take<Workload>),
+}
+
+impl Iterator for RequestStream {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            RequestStream::Trace(reader) => reader.next(),
+            RequestStream::Workload(workload) => workload.next().map(Ok),
+        }
+    }
+}
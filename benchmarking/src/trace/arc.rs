@@ -0,0 +1,138 @@
+use std::io::{self, BufRead};
+
+use crate::trace::{invalid_line, parse_field, Op, Request};
+
+/// ARC traces count blocks rather than bytes.
+const BLOCK_SIZE: u32 = 512;
+
+/// Reads the `.lis` block traces used in the ARC paper, "ARC: A Self-Tuning, Low Overhead
+/// Replacement Cache", which are also known as the UMass traces.
+///
+/// Each line is `starting_block number_of_blocks ignored request_number`, and stands for a get
+/// of each block in the range. Every block is 512 bytes.
+pub struct ArcReader<R> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    next_block: u64,
+    remaining_blocks: u64,
+    timestamp: u64,
+}
+
+impl<R: BufRead> ArcReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+            next_block: 0,
+            remaining_blocks: 0,
+            timestamp: 0,
+        }
+    }
+
+    /// Starts the line's range of blocks. A line with an error leaves the reader as it was, so
+    /// the next call moves on to the next line.
+    fn parse(&mut self, line: &str) -> io::Result<()> {
+        let mut fields = line.split_whitespace();
+        let next_block: u64 = parse_field(self.line_number, "starting block", fields.next())?;
+        let remaining_blocks: u64 =
+            parse_field(self.line_number, "number of blocks", fields.next())?;
+        if remaining_blocks > 0 && next_block.checked_add(remaining_blocks - 1).is_none() {
+            return Err(invalid_line(
+                self.line_number,
+                "the blocks run past the largest block number",
+            ));
+        }
+        let timestamp = match fields.nth(1) {
+            Some(request_number) => {
+                parse_field(self.line_number, "request number", Some(request_number))?
+            }
+            None => self.line_number as u64,
+        };
+        self.next_block = next_block;
+        self.remaining_blocks = remaining_blocks;
+        self.timestamp = timestamp;
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for ArcReader<R> {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining_blocks == 0 {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Err(error) = self.parse(&line) {
+                return Some(Err(error));
+            }
+        }
+
+        let block = self.next_block;
+        // Only wraps after the last block of a range that ends at the largest block number.
+        self.next_block = self.next_block.wrapping_add(1);
+        self.remaining_blocks -= 1;
+        Some(Ok(Request {
+            key: block,
+            size: BLOCK_SIZE,
+            op: Op::Get,
+            timestamp: self.timestamp,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::ArcReader;
+
+    #[test]
+    fn ranges_expand_into_blocks() {
+        let trace = "100 3 0 0\n7 1 0 1\n";
+        let requests: Vec<(u64, u64)> = ArcReader::new(trace.as_bytes())
+            .map(|request| request.map(|request| (request.key, request.timestamp)))
+            .collect::<Result<_, _>>()
+            .expect("trace is valid");
+        assert_eq!(requests, vec![(100, 0), (101, 0), (102, 0), (7, 1)]);
+    }
+
+    #[test]
+    fn ranges_past_the_largest_block_are_errors() {
+        let last = u64::MAX;
+        let trace = format!("{last} 1 0 0\n{last} 2 0 1\n");
+        let mut reader = ArcReader::new(trace.as_bytes());
+        assert_eq!(
+            reader.next().expect("one request").expect("valid").key,
+            last
+        );
+        let error = reader
+            .next()
+            .expect("an error")
+            .expect_err("range is too long");
+        assert!(error.to_string().contains("line 2"), "{error}");
+    }
+
+    #[test]
+    fn a_line_with_an_error_yields_none_of_its_blocks() {
+        let trace = format!("{} 2 0 0\n5 2 0 x\n9 1 0 3\n", u64::MAX);
+        let mut reader = ArcReader::new(trace.as_bytes());
+        assert!(
+            reader.next().expect("an error").is_err(),
+            "range is too long"
+        );
+        assert!(
+            reader.next().expect("an error").is_err(),
+            "bad request number"
+        );
+        assert_eq!(
+            reader.next().expect("one request").expect("valid").key,
+            9,
+            "the reader moves on to the next good line"
+        );
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::{self, BufRead};
+
+use crate::trace::{invalid_line, parse_field, parse_key, parse_op, Op, Request};
+
+/// Which columns of a generic CSV trace hold what. Columns count from 0.
+#[derive(Debug, Clone, clap::Args)]
+pub struct CsvColumns {
+    /// The CSV field delimiter.
+    #[arg(long = "csv-delimiter", default_value_t = ',')]
+    pub delimiter: char,
+    /// Skip the first line of the CSV.
+    #[arg(long = "csv-header")]
+    pub has_header: bool,
+    #[arg(long = "csv-key-column", default_value_t = 0)]
+    pub key: usize,
+    /// Without a size column, every object has a size of 1.
+    #[arg(long = "csv-size-column")]
+    pub size: Option<usize>,
+    /// Without an operation column, every request is a get.
+    #[arg(long = "csv-op-column")]
+    pub op: Option<usize>,
+    /// Without a timestamp column, requests are numbered in order.
+    #[arg(long = "csv-timestamp-column")]
+    pub timestamp: Option<usize>,
+}
+
+impl Default for CsvColumns {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            has_header: false,
+            key: 0,
+            size: None,
+            op: None,
+            timestamp: None,
+        }
+    }
+}
+
+/// Reads a CSV trace whose layout is described by [CsvColumns].
+///
+/// This is a plain split on the delimiter: quoted fields are not supported.
+pub struct CsvReader<R> {
+    lines: io::Lines<R>,
+    columns: CsvColumns,
+    line_number: usize,
+    requests: u64,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R, columns: CsvColumns) -> Self {
+        Self {
+            lines: reader.lines(),
+            columns,
+            line_number: 0,
+            requests: 0,
+        }
+    }
+
+    fn parse(&self, line: &str) -> io::Result<Request> {
+        let fields: Vec<&str> = line.split(self.columns.delimiter).collect();
+        let field = |index: usize| fields.get(index).map(|field| field.trim());
+
+        let key = field(self.columns.key)
+            .map(parse_key)
+            .ok_or_else(|| invalid_line(self.line_number, "missing key"))?;
+        let size = match self.columns.size {
+            Some(column) => parse_field(self.line_number, "size", field(column))?,
+            None => 1,
+        };
+        let op = match self.columns.op {
+            Some(column) => field(column).and_then(parse_op).ok_or_else(|| {
+                invalid_line(
+                    self.line_number,
+                    format!("invalid operation {:?}", field(column)),
+                )
+            })?,
+            None => Op::Get,
+        };
+        let timestamp = match self.columns.timestamp {
+            Some(column) => parse_field(self.line_number, "timestamp", field(column))?,
+            None => self.requests,
+        };
+
+        Ok(Request {
+            key,
+            size,
+            op,
+            timestamp,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() || (self.columns.has_header && self.line_number == 1) {
+                continue;
+            }
+            let request = self.parse(&line);
+            self.requests += 1;
+            return Some(request);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::{CsvColumns, CsvReader, Op, Request};
+
+    #[test]
+    fn configured_columns() {
+        let trace = "time;op;size;key\n10;set;300;a\n11;get;300;a\n";
+        let columns = CsvColumns {
+            delimiter: ';',
+            has_header: true,
+            key: 3,
+            size: Some(2),
+            op: Some(1),
+            timestamp: Some(0),
+        };
+        let requests: Vec<Request> = CsvReader::new(trace.as_bytes(), columns)
+            .collect::<Result<_, _>>()
+            .expect("trace is valid");
+        let ops: Vec<(Op, u32, u64)> = requests
+            .iter()
+            .map(|request| (request.op, request.size, request.timestamp))
+            .collect();
+        assert_eq!(ops, vec![(Op::Set, 300, 10), (Op::Get, 300, 11)]);
+        assert_eq!(requests[0].key, requests[1].key);
+    }
+
+    #[test]
+    fn defaults_are_keys_only() {
+        let requests: Vec<Request> = CsvReader::new("5\n6\n".as_bytes(), CsvColumns::default())
+            .collect::<Result<_, _>>()
+            .expect("trace is valid");
+        assert_eq!(requests[1].key, 6);
+        assert_eq!(requests[1].size, 1);
+        assert_eq!(requests[1].op, Op::Get);
+        assert_eq!(requests[1].timestamp, 1);
+    }
+}
@@ -0,0 +1,95 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+use flate2::bufread::MultiGzDecoder;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// A trace file's bytes, decompressed if the file is gzip or zstd compressed.
+///
+/// The compression is detected from the first bytes of the file rather than its name, so a
+/// `.lis.gz` and a `.lis` renamed to `.lis.zst` by mistake both just work.
+pub enum TraceInput<R: Read> {
+    Plain(BufReader<R>),
+    Gzip(BufReader<MultiGzDecoder<BufReader<R>>>),
+    Zstd(BufReader<zstd::Decoder<'static, BufReader<R>>>),
+}
+
+impl<R: Read> TraceInput<R> {
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut reader = BufReader::new(reader);
+        let magic = reader.fill_buf()?;
+        Ok(if magic.starts_with(&GZIP_MAGIC) {
+            TraceInput::Gzip(BufReader::new(MultiGzDecoder::new(reader)))
+        } else if magic.starts_with(&ZSTD_MAGIC) {
+            TraceInput::Zstd(BufReader::new(zstd::Decoder::with_buffer(reader)?))
+        } else {
+            TraceInput::Plain(reader)
+        })
+    }
+}
+
+impl<R: Read> Read for TraceInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            TraceInput::Plain(reader) => reader.read(buf),
+            TraceInput::Gzip(reader) => reader.read(buf),
+            TraceInput::Zstd(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: Read> BufRead for TraceInput<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            TraceInput::Plain(reader) => reader.fill_buf(),
+            TraceInput::Gzip(reader) => reader.fill_buf(),
+            TraceInput::Zstd(reader) => reader.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        match self {
+            TraceInput::Plain(reader) => reader.consume(amount),
+            TraceInput::Gzip(reader) => reader.consume(amount),
+            TraceInput::Zstd(reader) => reader.consume(amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+
+    use crate::trace::TraceInput;
+
+    const TRACE: &str = "1 100\n2 200\n";
+
+    fn read(input: &[u8]) -> String {
+        let mut text = String::new();
+        TraceInput::new(input)
+            .expect("input can be opened")
+            .read_to_string(&mut text)
+            .expect("input can be read");
+        text
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(read(TRACE.as_bytes()), TRACE);
+    }
+
+    #[test]
+    fn gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(TRACE.as_bytes()).expect("gzip works");
+        let compressed = encoder.finish().expect("gzip works");
+        assert_eq!(read(&compressed), TRACE);
+    }
+
+    #[test]
+    fn zstd() {
+        let compressed = zstd::encode_all(TRACE.as_bytes(), 1).expect("zstd works");
+        assert_eq!(read(&compressed), TRACE);
+    }
+}
@@ -0,0 +1,151 @@
+//! Streaming readers for the cache trace formats that the cache research community publishes.
+//!
+//! Every format produces the same [Request] items, so the simulator doesn't care where a trace
+//! came from. Traces can be huge, so the readers stream them rather than reading them whole, and
+//! gzip or zstd compressed files are decompressed on the fly.
+
+use std::{
+    fs::File,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, Read},
+    path::Path,
+};
+
+mod arc;
+mod csv;
+mod input;
+mod oracle_general;
+mod text;
+mod twitter;
+
+pub use arc::ArcReader;
+pub use csv::{CsvColumns, CsvReader};
+pub use input::TraceInput;
+pub use oracle_general::OracleGeneralReader;
+pub use text::TextReader;
+pub use twitter::TwitterReader;
+
+/// What a request does to the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Get,
+    Set,
+    Delete,
+}
+
+/// One request in a cache trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Request {
+    pub key: u64,
+    /// The size of the requested object in bytes.
+    pub size: u32,
+    pub op: Op,
+    /// When the request happened, in whatever unit the trace uses. Traces without timestamps
+    /// count requests instead.
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TraceFormat {
+    /// One request per line: a key, then optionally a size in bytes.
+    Text,
+    /// libCacheSim's binary oracleGeneral records.
+    OracleGeneral,
+    /// The Twitter cache cluster traces.
+    Twitter,
+    /// The ARC paper's `.lis` block traces.
+    Arc,
+    /// Any CSV, with the columns given by the `--csv-*` options.
+    Csv,
+}
+
+/// A reader for any supported trace format.
+pub enum TraceReader<R: Read> {
+    Text(TextReader<TraceInput<R>>),
+    OracleGeneral(OracleGeneralReader<TraceInput<R>>),
+    Twitter(TwitterReader<TraceInput<R>>),
+    Arc(ArcReader<TraceInput<R>>),
+    Csv(CsvReader<TraceInput<R>>),
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Reads a trace of the given format, which may be gzip or zstd compressed.
+    pub fn new(reader: R, format: TraceFormat, csv_columns: &CsvColumns) -> io::Result<Self> {
+        let input = TraceInput::new(reader)?;
+        Ok(match format {
+            TraceFormat::Text => TraceReader::Text(TextReader::new(input)),
+            TraceFormat::OracleGeneral => {
+                TraceReader::OracleGeneral(OracleGeneralReader::new(input))
+            }
+            TraceFormat::Twitter => TraceReader::Twitter(TwitterReader::new(input)),
+            TraceFormat::Arc => TraceReader::Arc(ArcReader::new(input)),
+            TraceFormat::Csv => TraceReader::Csv(CsvReader::new(input, csv_columns.clone())),
+        })
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            TraceReader::Text(reader) => reader.next(),
+            TraceReader::OracleGeneral(reader) => reader.next(),
+            TraceReader::Twitter(reader) => reader.next(),
+            TraceReader::Arc(reader) => reader.next(),
+            TraceReader::Csv(reader) => reader.next(),
+        }
+    }
+}
+
+/// Opens a trace file of the given format, which may be gzip or zstd compressed.
+pub fn open_trace(
+    path: impl AsRef<Path>,
+    format: TraceFormat,
+    csv_columns: &CsvColumns,
+) -> io::Result<TraceReader<File>> {
+    TraceReader::new(File::open(path)?, format, csv_columns)
+}
+
+/// Uses numeric keys as they are, and hashes anything else.
+pub fn parse_key(key: &str) -> u64 {
+    key.parse().unwrap_or_else(|_| {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    })
+}
+
+/// Understands the memcached-style operation names used by the Twitter traces, and a few
+/// common synonyms.
+pub fn parse_op(op: &str) -> Option<Op> {
+    match op.to_ascii_lowercase().as_str() {
+        "get" | "gets" | "read" => Some(Op::Get),
+        "set" | "add" | "replace" | "cas" | "append" | "prepend" | "incr" | "decr" | "write" => {
+            Some(Op::Set)
+        }
+        "delete" | "del" => Some(Op::Delete),
+        _ => None,
+    }
+}
+
+/// An error for a line of a text trace that doesn't parse.
+fn invalid_line(line_number: usize, message: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("line {line_number}: {message}"),
+    )
+}
+
+/// Parses a numeric field of a text trace.
+fn parse_field<T>(line_number: usize, name: &str, field: Option<&str>) -> io::Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let field = field.ok_or_else(|| invalid_line(line_number, format!("missing {name}")))?;
+    field
+        .trim()
+        .parse()
+        .map_err(|error| invalid_line(line_number, format!("invalid {name} {field:?}: {error}")))
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Read};
+
+use crate::trace::{Op, Request};
+
+/// The size of one packed, little-endian oracleGeneral record:
+/// `u32 clock_time, u64 obj_id, u32 obj_size, i64 next_access_vtime`.
+const RECORD_SIZE: usize = 24;
+
+/// Reads libCacheSim's binary oracleGeneral traces, as published for the SIEVE and S3-FIFO papers.
+///
+/// Every request is a get. The records also carry the index of each object's next access, which
+/// this reader skips: the simulator computes next uses itself for Belady, for every format.
+pub struct OracleGeneralReader<R> {
+    reader: R,
+}
+
+impl<R: Read> OracleGeneralReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+}
+
+impl<R: Read> Iterator for OracleGeneralReader<R> {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = [0_u8; RECORD_SIZE];
+        let mut filled = 0;
+        while filled < RECORD_SIZE {
+            match self.reader.read(&mut record[filled..]) {
+                Ok(0) if filled == 0 => return None,
+                Ok(0) => {
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "trace ends partway through a record: {filled} of {RECORD_SIZE} bytes"
+                        ),
+                    )))
+                }
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+
+        let clock_time = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+        let mut obj_id = [0_u8; 8];
+        obj_id.copy_from_slice(&record[4..12]);
+        let obj_size = u32::from_le_bytes([record[12], record[13], record[14], record[15]]);
+
+        Some(Ok(Request {
+            key: u64::from_le_bytes(obj_id),
+            size: obj_size,
+            op: Op::Get,
+            timestamp: u64::from(clock_time),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::{Op, OracleGeneralReader, Request};
+
+    fn record(clock_time: u32, obj_id: u64, obj_size: u32, next_access_vtime: i64) -> Vec<u8> {
+        [
+            &clock_time.to_le_bytes()[..],
+            &obj_id.to_le_bytes(),
+            &obj_size.to_le_bytes(),
+            &next_access_vtime.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn records() {
+        let trace = [record(7, 42, 1000, 1), record(8, 42, 1000, -1)].concat();
+        let requests: Vec<Request> = OracleGeneralReader::new(trace.as_slice())
+            .collect::<Result<_, _>>()
+            .expect("trace is valid");
+        assert_eq!(
+            requests,
+            vec![
+                Request {
+                    key: 42,
+                    size: 1000,
+                    op: Op::Get,
+                    timestamp: 7
+                },
+                Request {
+                    key: 42,
+                    size: 1000,
+                    op: Op::Get,
+                    timestamp: 8
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn truncated_record_is_an_error() {
+        let trace = record(7, 42, 1000, 1);
+        let mut reader = OracleGeneralReader::new(&trace[..20]);
+        assert!(reader.next().expect("there is a record").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{self, BufRead};
+
+use crate::trace::{parse_field, parse_key, Op, Request};
+
+/// Reads a plain text trace with one request per line: a key, then optionally a size in bytes,
+/// separated by whitespace or a comma. Keys that are not numbers are hashed into numbers.
+/// Requests without a size have a size of 1. Blank lines and lines starting with `#` are skipped.
+///
+/// Every request is a get, and its timestamp is its position in the trace.
+pub struct TextReader<R> {
+    lines: io::Lines<R>,
+    line_number: usize,
+    requests: u64,
+}
+
+impl<R: BufRead> TextReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+            requests: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for TextReader<R> {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            self.line_number += 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|field| !field.is_empty());
+            let key = fields.next().map(parse_key).unwrap_or_default();
+            let size = match fields.next() {
+                Some(size) => match parse_field(self.line_number, "size", Some(size)) {
+                    Ok(size) => size,
+                    Err(error) => return Some(Err(error)),
+                },
+                None => 1,
+            };
+
+            let timestamp = self.requests;
+            self.requests += 1;
+            return Some(Ok(Request {
+                key,
+                size,
+                op: Op::Get,
+                timestamp,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::{parse_key, Op, Request, TextReader};
+
+    #[test]
+    fn text_trace() {
+        let trace = "# key size\n1 100\n2,200\n\nthree\n";
+        let requests: Vec<Request> = TextReader::new(trace.as_bytes())
+            .collect::<Result<_, _>>()
+            .expect("trace is valid");
+        let keys_and_sizes: Vec<(u64, u32)> = requests
+            .iter()
+            .map(|request| (request.key, request.size))
+            .collect();
+        assert_eq!(
+            keys_and_sizes,
+            vec![(1, 100), (2, 200), (parse_key("three"), 1)]
+        );
+        assert!(requests.iter().all(|request| request.op == Op::Get));
+        assert_eq!(requests[2].timestamp, 2);
+    }
+
+    #[test]
+    fn invalid_size_reports_the_line() {
+        let error = TextReader::new("1 100\n2 big\n".as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .expect_err("size is invalid");
+        assert!(error.to_string().starts_with("line 2"), "{error}");
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::{self, BufRead};
+
+use crate::trace::{invalid_line, parse_field, parse_key, parse_op, Request};
+
+/// Reads the Twitter cache cluster traces from "A large scale analysis of hundreds of in-memory
+/// cache clusters at Twitter". Each line is
+/// `timestamp,anonymized key,key size,value size,client id,operation,TTL`.
+///
+/// The object size is the key size plus the value size.
+pub struct TwitterReader<R> {
+    lines: io::Lines<R>,
+    line_number: usize,
+}
+
+impl<R: BufRead> TwitterReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            line_number: 0,
+        }
+    }
+
+    fn parse(&self, line: &str) -> io::Result<Request> {
+        let fields: Vec<&str> = line.split(',').collect();
+        let field = |index: usize| fields.get(index).copied();
+
+        let timestamp = parse_field(self.line_number, "timestamp", field(0))?;
+        let key = parse_key(field(1).unwrap_or_default());
+        let key_size: u32 = parse_field(self.line_number, "key size", field(2))?;
+        let value_size: u32 = parse_field(self.line_number, "value size", field(3))?;
+        let op = field(5).and_then(parse_op).ok_or_else(|| {
+            invalid_line(
+                self.line_number,
+                format!("invalid operation {:?}", field(5)),
+            )
+        })?;
+
+        Ok(Request {
+            key,
+            size: key_size.saturating_add(value_size),
+            op,
+            timestamp,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for TwitterReader<R> {
+    type Item = io::Result<Request>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(self.parse(&line));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::{parse_key, Op, Request, TwitterReader};
+
+    #[test]
+    fn twitter_trace() {
+        let trace = "0,q:q:1:8WTfjZU,14,2,11,get,0\n1,q:q:1:8WTfjZU,14,300,11,set,3600\n2,q:q:1:8WTfjZU,14,0,11,delete,0\n";
+        let requests: Vec<Request> = TwitterReader::new(trace.as_bytes())
+            .collect::<Result<_, _>>()
+            .expect("trace is valid");
+        let key = parse_key("q:q:1:8WTfjZU");
+        assert_eq!(
+            requests,
+            vec![
+                Request {
+                    key,
+                    size: 16,
+                    op: Op::Get,
+                    timestamp: 0
+                },
+                Request {
+                    key,
+                    size: 314,
+                    op: Op::Set,
+                    timestamp: 1
+                },
+                Request {
+                    key,
+                    size: 14,
+                    op: Op::Delete,
+                    timestamp: 2
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_operation_is_an_error() {
+        let mut reader = TwitterReader::new("0,key,1,1,1,frobnicate,0\n".as_bytes());
+        let error = reader.next().expect("there is a line").expect_err("bad op");
+        assert!(error.to_string().starts_with("line 1"), "{error}");
+    }
+}
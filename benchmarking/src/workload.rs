@@ -0,0 +1,565 @@
+//! Synthetic, seeded workloads.
+//!
+//! Drawing keys uniformly makes every eviction policy look the same, because no key is any more
+//! worth keeping than another. Real workloads are skewed, they scan, they loop, they are full of
+//! keys that are requested once and never again, and their popular keys change over time. These
+//! generators produce each of those patterns as a reproducible stream of [Request]s, for the
+//! criterion benches and for the simulator alike.
+
+use std::{io, str::FromStr};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::{Distribution, Zipf};
+
+use crate::trace::{Op, Request};
+
+/// How keys are drawn. Keys in `0..key_space` are the "normal" keys of a workload; scans and
+/// one-hit wonders use keys above the key space so they never collide with them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyDistribution {
+    /// Every key is equally likely.
+    Uniform { key_space: u64 },
+    /// Key `k` is requested in proportion to `1 / (k + 1)^alpha`. Bigger alphas are more skewed.
+    Zipf { key_space: u64, alpha: f64 },
+    /// Zipf-distributed requests, with a sequential scan of `scan_length` cold keys after every
+    /// `scan_every` requests, like a batch job sharing a cache with a service.
+    Scan {
+        key_space: u64,
+        alpha: f64,
+        scan_length: u64,
+        scan_every: u64,
+    },
+    /// Keys `0..loop_length` over and over. LRU's worst case when the loop doesn't fit.
+    Loop { loop_length: u64 },
+    /// Zipf-distributed requests, except that a `one_hit_fraction` share of requests are for
+    /// brand new keys that are never requested again.
+    OneHitWonders {
+        key_space: u64,
+        alpha: f64,
+        one_hit_fraction: f64,
+    },
+    /// A `hot_fraction` share of requests go to a window of `hot_set_size` keys, and the rest are
+    /// uniform. The window moves to the next `hot_set_size` keys after every `shift_every`
+    /// requests.
+    ShiftingHotspot {
+        key_space: u64,
+        hot_set_size: u64,
+        hot_fraction: f64,
+        shift_every: u64,
+    },
+}
+
+/// How big each key's value is. Every key keeps the same size for the whole workload.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeDistribution {
+    Fixed(u32),
+    /// Uniform between `min` and `max`, inclusive.
+    Uniform {
+        min: u32,
+        max: u32,
+    },
+    /// Heavy tailed: mostly near `min`, occasionally huge, capped at `max`.
+    Pareto {
+        min: u32,
+        shape: f64,
+        max: u32,
+    },
+}
+
+/// Everything needed to reproduce a workload.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkloadSpec {
+    pub keys: KeyDistribution,
+    pub sizes: SizeDistribution,
+    /// The share of requests that are gets. The rest are sets.
+    pub read_ratio: f64,
+    pub seed: u64,
+}
+
+impl WorkloadSpec {
+    /// A workload that only reads, with fixed size values and the default seed.
+    pub fn new(keys: KeyDistribution) -> Self {
+        Self {
+            keys,
+            sizes: SizeDistribution::Fixed(1),
+            read_ratio: 1.0,
+            seed: 37,
+        }
+    }
+
+    pub fn with_sizes(self, sizes: SizeDistribution) -> Self {
+        Self { sizes, ..self }
+    }
+
+    pub fn with_read_ratio(self, read_ratio: f64) -> Self {
+        Self { read_ratio, ..self }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+
+    /// The first `count` requests, each with its key formatted as the string the benchmarks
+    /// cache.
+    pub fn keyed_requests(&self, count: usize) -> Vec<(Request, String)> {
+        self.generate()
+            .take(count)
+            .map(|request| (request, format!("value {}", request.key)))
+            .collect()
+    }
+
+    /// An endless stream of requests. Use `take` to get a trace of a given length.
+    pub fn generate(&self) -> Workload {
+        let zipf = match self.keys {
+            KeyDistribution::Zipf {
+                key_space, alpha, ..
+            }
+            | KeyDistribution::Scan {
+                key_space, alpha, ..
+            }
+            | KeyDistribution::OneHitWonders {
+                key_space, alpha, ..
+            } => Some(Zipf::new(key_space.max(1), alpha).expect("alpha must not be negative")),
+            KeyDistribution::Uniform { .. }
+            | KeyDistribution::Loop { .. }
+            | KeyDistribution::ShiftingHotspot { .. } => None,
+        };
+        Workload {
+            spec: self.clone(),
+            random: StdRng::seed_from_u64(self.seed),
+            zipf,
+            requests: 0,
+            one_hit_wonders: 0,
+        }
+    }
+}
+
+/// A stream of requests following a [WorkloadSpec].
+pub struct Workload {
+    spec: WorkloadSpec,
+    random: StdRng,
+    zipf: Option<Zipf<f64>>,
+    requests: u64,
+    one_hit_wonders: u64,
+}
+
+impl Workload {
+    fn next_key(&mut self) -> u64 {
+        let index = self.requests;
+        match self.spec.keys {
+            KeyDistribution::Uniform { key_space } => self.random.gen_range(0..key_space.max(1)),
+            KeyDistribution::Zipf { .. } => self.zipf_key(),
+            KeyDistribution::Scan {
+                key_space,
+                scan_length,
+                scan_every,
+                ..
+            } => {
+                let position = index % (scan_every + scan_length).max(1);
+                if position < scan_every {
+                    self.zipf_key()
+                } else {
+                    key_space + (position - scan_every)
+                }
+            }
+            KeyDistribution::Loop { loop_length } => index % loop_length.max(1),
+            KeyDistribution::OneHitWonders {
+                key_space,
+                one_hit_fraction,
+                ..
+            } => {
+                if self.random.gen_bool(one_hit_fraction) {
+                    self.one_hit_wonders += 1;
+                    key_space + self.one_hit_wonders
+                } else {
+                    self.zipf_key()
+                }
+            }
+            KeyDistribution::ShiftingHotspot {
+                key_space,
+                hot_set_size,
+                hot_fraction,
+                shift_every,
+            } => {
+                let key_space = key_space.max(1);
+                if self.random.gen_bool(hot_fraction) {
+                    let window_start = (index / shift_every.max(1)) * hot_set_size;
+                    (window_start + self.random.gen_range(0..hot_set_size.max(1))) % key_space
+                } else {
+                    self.random.gen_range(0..key_space)
+                }
+            }
+        }
+    }
+
+    /// Zipf ranks start at 1; keys start at 0.
+    fn zipf_key(&mut self) -> u64 {
+        match &self.zipf {
+            Some(zipf) => zipf.sample(&mut self.random) as u64 - 1,
+            None => 0,
+        }
+    }
+
+    /// Derives a key's size from a hash of the key, so it's the same every time the key appears.
+    fn size_of(&self, key: u64) -> u32 {
+        let uniform = unit_interval(split_mix(key ^ self.spec.seed));
+        match self.spec.sizes {
+            SizeDistribution::Fixed(size) => size,
+            SizeDistribution::Uniform { min, max } => {
+                let span = f64::from(max.saturating_sub(min)) + 1.0;
+                min + ((uniform * span) as u32).min(max.saturating_sub(min))
+            }
+            SizeDistribution::Pareto { min, shape, max } => {
+                let size = f64::from(min.max(1)) / (1.0 - uniform).powf(1.0 / shape);
+                size.min(f64::from(max)) as u32
+            }
+        }
+    }
+}
+
+impl Iterator for Workload {
+    type Item = Request;
+
+    fn next(&mut self) -> Option<Request> {
+        let key = self.next_key();
+        let op = if self.random.gen_bool(self.spec.read_ratio) {
+            Op::Get
+        } else {
+            Op::Set
+        };
+        let request = Request {
+            key,
+            size: self.size_of(key),
+            op,
+            timestamp: self.requests,
+        };
+        self.requests += 1;
+        Some(request)
+    }
+}
+
+/// A fast, well-mixed hash of a number. See <https://prng.di.unimi.it/splitmix64.c>.
+pub(crate) fn split_mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Maps a hash onto `[0, 1)`.
+fn unit_interval(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// Parses `N` as a fixed size, `MIN..MAX` as a uniform range, and `pareto:MIN:SHAPE:MAX` as a
+/// Pareto distribution.
+impl FromStr for SizeDistribution {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let number = |field: &str| {
+            field
+                .trim()
+                .parse::<u32>()
+                .map_err(|error| format!("invalid size {field:?}: {error}"))
+        };
+        if let Some(pareto) = text.strip_prefix("pareto:") {
+            let fields: Vec<&str> = pareto.split(':').collect();
+            let [min, shape, max] = fields[..] else {
+                return Err(format!("expected pareto:MIN:SHAPE:MAX, not {text:?}"));
+            };
+            let shape: f64 = shape
+                .parse()
+                .map_err(|error| format!("invalid shape {shape:?}: {error}"))?;
+            if shape <= 0.0 {
+                return Err(format!("shape must be positive, not {shape}"));
+            }
+            Ok(SizeDistribution::Pareto {
+                min: number(min)?,
+                shape,
+                max: number(max)?,
+            })
+        } else if let Some((min, max)) = text.split_once("..") {
+            Ok(SizeDistribution::Uniform {
+                min: number(min)?,
+                max: number(max)?,
+            })
+        } else {
+            Ok(SizeDistribution::Fixed(number(text)?))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WorkloadKind {
+    Uniform,
+    Zipf,
+    Scan,
+    Loop,
+    OneHitWonders,
+    ShiftingHotspot,
+}
+
+/// Command line options for a synthetic workload. Options that don't apply to the chosen
+/// workload are ignored, and the shape options default to sizes relative to the key space.
+#[derive(Debug, Clone, clap::Args)]
+pub struct WorkloadArgs {
+    #[arg(long, value_enum, default_value_t = WorkloadKind::Zipf)]
+    pub workload: WorkloadKind,
+    /// How many requests to generate.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub requests: usize,
+    /// How many distinct keys the workload normally draws from.
+    #[arg(long, default_value_t = 10_000)]
+    pub key_space: u64,
+    /// Zipf skew, for zipf, scan and one-hit-wonders.
+    #[arg(long, default_value_t = 1.0)]
+    pub alpha: f64,
+    /// The length of each scan. Defaults to the key space.
+    #[arg(long)]
+    pub scan_length: Option<u64>,
+    /// Requests between scans. Defaults to 10 times the key space.
+    #[arg(long)]
+    pub scan_every: Option<u64>,
+    /// The share of requests for keys that are never requested again.
+    #[arg(long, default_value_t = 0.5)]
+    pub one_hit_fraction: f64,
+    /// The size of the shifting hot set. Defaults to 1% of the key space.
+    #[arg(long)]
+    pub hot_set_size: Option<u64>,
+    /// The share of requests that go to the shifting hot set.
+    #[arg(long, default_value_t = 0.9)]
+    pub hot_fraction: f64,
+    /// Requests between hot set shifts. Defaults to 10 times the key space.
+    #[arg(long)]
+    pub shift_every: Option<u64>,
+    /// Value sizes in bytes: `N`, `MIN..MAX` or `pareto:MIN:SHAPE:MAX`.
+    #[arg(long, default_value = "1")]
+    pub value_size: SizeDistribution,
+    /// The share of requests that are gets rather than sets.
+    #[arg(long, default_value_t = 0.8)]
+    pub read_ratio: f64,
+    #[arg(long, default_value_t = 37)]
+    pub seed: u64,
+}
+
+impl WorkloadArgs {
+    /// The workload the options describe. Options the workload uses that are out of range, and
+    /// that would otherwise panic while it's generated, are an error.
+    pub fn spec(&self) -> io::Result<WorkloadSpec> {
+        let invalid = |message: String| Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        let uses_alpha = matches!(
+            self.workload,
+            WorkloadKind::Zipf | WorkloadKind::Scan | WorkloadKind::OneHitWonders
+        );
+        if uses_alpha && (self.alpha.is_nan() || self.alpha < 0.0) {
+            return invalid(format!("alpha can't be negative, not {}", self.alpha));
+        }
+        let fractions = match self.workload {
+            WorkloadKind::OneHitWonders => Some(("one-hit-fraction", self.one_hit_fraction)),
+            WorkloadKind::ShiftingHotspot => Some(("hot-fraction", self.hot_fraction)),
+            _ => None,
+        };
+        for (name, fraction) in fractions
+            .into_iter()
+            .chain([("read-ratio", self.read_ratio)])
+        {
+            if !(0.0..=1.0).contains(&fraction) {
+                return invalid(format!("{name} must be between 0 and 1, not {fraction}"));
+            }
+        }
+
+        let key_space = self.key_space.max(1);
+        let keys = match self.workload {
+            WorkloadKind::Uniform => KeyDistribution::Uniform { key_space },
+            WorkloadKind::Zipf => KeyDistribution::Zipf {
+                key_space,
+                alpha: self.alpha,
+            },
+            WorkloadKind::Scan => KeyDistribution::Scan {
+                key_space,
+                alpha: self.alpha,
+                scan_length: self.scan_length.unwrap_or(key_space),
+                scan_every: self.scan_every.unwrap_or(10 * key_space),
+            },
+            WorkloadKind::Loop => KeyDistribution::Loop {
+                loop_length: key_space,
+            },
+            WorkloadKind::OneHitWonders => KeyDistribution::OneHitWonders {
+                key_space,
+                alpha: self.alpha,
+                one_hit_fraction: self.one_hit_fraction,
+            },
+            WorkloadKind::ShiftingHotspot => KeyDistribution::ShiftingHotspot {
+                key_space,
+                hot_set_size: self.hot_set_size.unwrap_or((key_space / 100).max(1)),
+                hot_fraction: self.hot_fraction,
+                shift_every: self.shift_every.unwrap_or(10 * key_space),
+            },
+        };
+        Ok(WorkloadSpec::new(keys)
+            .with_sizes(self.value_size)
+            .with_read_ratio(self.read_ratio)
+            .with_seed(self.seed))
+    }
+
+    /// Generates the requested number of requests.
+    pub fn requests(&self) -> io::Result<Vec<Request>> {
+        Ok(self.spec()?.generate().take(self.requests).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use clap::Parser;
+
+    use crate::{
+        trace::Op,
+        workload::{KeyDistribution, SizeDistribution, WorkloadArgs, WorkloadSpec},
+    };
+
+    fn keys(spec: &WorkloadSpec, count: usize) -> Vec<u64> {
+        spec.generate()
+            .take(count)
+            .map(|request| request.key)
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_stream() {
+        let spec = WorkloadSpec::new(KeyDistribution::Zipf {
+            key_space: 1000,
+            alpha: 0.8,
+        });
+        assert_eq!(keys(&spec, 1000), keys(&spec, 1000));
+        assert_ne!(keys(&spec, 1000), keys(&spec.clone().with_seed(1), 1000));
+    }
+
+    #[test]
+    fn zipf_is_skewed() {
+        let spec = WorkloadSpec::new(KeyDistribution::Zipf {
+            key_space: 1000,
+            alpha: 1.0,
+        });
+        let keys = keys(&spec, 10_000);
+        let top_ten = keys.iter().filter(|&&key| key < 10).count();
+        assert!(3_000 < top_ten, "the top 1% of keys got {top_ten} requests");
+        assert!(keys.iter().all(|&key| key < 1000));
+    }
+
+    #[test]
+    fn scan_and_loop() {
+        let scan = WorkloadSpec::new(KeyDistribution::Scan {
+            key_space: 100,
+            alpha: 1.0,
+            scan_length: 3,
+            scan_every: 5,
+        });
+        assert_eq!(keys(&scan, 16)[5..8], [100, 101, 102]);
+        assert_eq!(keys(&scan, 16)[13..16], [100, 101, 102]);
+
+        let looping = WorkloadSpec::new(KeyDistribution::Loop { loop_length: 3 });
+        assert_eq!(keys(&looping, 7), vec![0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn one_hit_wonders_are_unique() {
+        let spec = WorkloadSpec::new(KeyDistribution::OneHitWonders {
+            key_space: 100,
+            alpha: 1.0,
+            one_hit_fraction: 0.5,
+        });
+        let wonders: Vec<u64> = keys(&spec, 1000)
+            .into_iter()
+            .filter(|&key| 100 <= key)
+            .collect();
+        let distinct: HashSet<&u64> = wonders.iter().collect();
+        assert_eq!(wonders.len(), distinct.len());
+        assert!(400 < wonders.len() && wonders.len() < 600);
+    }
+
+    #[test]
+    fn hotspot_shifts() {
+        let spec = WorkloadSpec::new(KeyDistribution::ShiftingHotspot {
+            key_space: 1000,
+            hot_set_size: 10,
+            hot_fraction: 1.0,
+            shift_every: 100,
+        });
+        let keys = keys(&spec, 200);
+        assert!(keys[..100].iter().all(|&key| key < 10));
+        assert!(keys[100..].iter().all(|&key| (10..20).contains(&key)));
+    }
+
+    #[test]
+    fn sizes_and_ops() {
+        let spec = WorkloadSpec::new(KeyDistribution::Uniform { key_space: 50 })
+            .with_sizes("100..200".parse().expect("size distribution is valid"))
+            .with_read_ratio(0.75);
+        let requests: Vec<_> = spec.generate().take(4000).collect();
+        for request in &requests {
+            assert!((100..=200).contains(&request.size));
+            let again = requests
+                .iter()
+                .find(|other| other.key == request.key)
+                .expect("the request itself matches");
+            assert_eq!(again.size, request.size, "sizes are stable per key");
+        }
+        let gets = requests
+            .iter()
+            .filter(|request| request.op == Op::Get)
+            .count();
+        assert!(2800 < gets && gets < 3200, "{gets} gets");
+
+        assert_eq!(
+            "pareto:10:1.5:1000".parse(),
+            Ok(SizeDistribution::Pareto {
+                min: 10,
+                shape: 1.5,
+                max: 1000
+            })
+        );
+        assert!("1..big".parse::<SizeDistribution>().is_err());
+    }
+
+    #[test]
+    fn out_of_range_options_are_errors() {
+        #[derive(Parser)]
+        struct Arguments {
+            #[command(flatten)]
+            workload: WorkloadArgs,
+        }
+        let spec = |line: &str| {
+            Arguments::parse_from(std::iter::once("test").chain(line.split(' ')))
+                .workload
+                .spec()
+                .map_err(|error| error.to_string())
+        };
+
+        assert!(spec("--workload zipf --alpha=0.8").is_ok());
+        assert_eq!(
+            spec("--workload scan --alpha=-1").unwrap_err(),
+            "alpha can't be negative, not -1"
+        );
+        assert_eq!(
+            spec("--workload one-hit-wonders --one-hit-fraction 1.5").unwrap_err(),
+            "one-hit-fraction must be between 0 and 1, not 1.5"
+        );
+        assert_eq!(
+            spec("--workload shifting-hotspot --hot-fraction=-0.1").unwrap_err(),
+            "hot-fraction must be between 0 and 1, not -0.1"
+        );
+        for read_ratio in ["NaN", "1.5", "-1"] {
+            assert_eq!(
+                spec(&format!("--workload uniform --read-ratio={read_ratio}")).unwrap_err(),
+                format!("read-ratio must be between 0 and 1, not {read_ratio}")
+            );
+        }
+        assert!(
+            spec("--workload uniform --alpha=-1 --hot-fraction 2").is_ok(),
+            "options the workload doesn't use are ignored"
+        );
+    }
+}
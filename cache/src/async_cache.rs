@@ -0,0 +1,36 @@
+use std::{future::Future, sync::Arc};
+
+/// Describes a cache that async tasks can share without blocking their executor's threads.
+///
+/// This mirrors [ShareableCache](crate::ShareableCache), but every method returns a future, so an
+/// implementation can wait for a lock or a load without holding up the thread it runs on.
+///
+/// The methods are `async fn`s written out as functions returning `impl Future + Send`, so that
+/// the futures can be spawned onto a multi-threaded runtime. Implementations can still write them
+/// as `async fn`s.
+pub trait AsyncCache<Key, Value>: Send + Sync
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    /// Gets a value from the cache, or None if it is not present (or has been evicted).
+    fn get(&self, key: &Key) -> impl Future<Output = Option<Value>> + Send;
+
+    /// Sets a value in the cache.
+    fn set(&self, key: Key, value: Value) -> impl Future<Output = ()> + Send;
+
+    /// Gets a value from the cache, or loads it with `init` and caches it if it is not present.
+    ///
+    /// Concurrent callers for the same key share one load: only the first caller's `init` runs,
+    /// and every caller gets its result. A loader error is returned to every caller, and by
+    /// default nothing is cached, though implementations may cache errors if configured to.
+    /// Dropping a caller's future does not cancel the load for the others.
+    fn get_or_try_insert_with<Init, Error>(
+        &self,
+        key: Key,
+        init: Init,
+    ) -> impl Future<Output = Result<Value, Arc<Error>>> + Send
+    where
+        Init: Future<Output = Result<Value, Error>> + Send + 'static,
+        Error: Send + Sync + 'static;
+}
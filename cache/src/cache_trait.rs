@@ -15,3 +15,24 @@ where
     /// Sets a value in the cache.
     fn set(&mut self, key: Key, value: Value);
 }
+
+/// A cache that can forget keys when asked to, as well as by evicting them. Not every eviction
+/// policy can take a key out of the middle of its bookkeeping cheaply, so caches opt in.
+pub trait RemovableCache<Key, Value>: SizeLimitedCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+{
+    /// Removes a key from the cache, returning its value, or None if it was not present.
+    fn remove(&mut self, key: &Key) -> Option<Value>;
+
+    /// Removes every key from the cache.
+    fn clear(&mut self);
+}
+
+/// A cache that can list the keys it holds without marking them as used, so a test can see
+/// exactly which key each set evicted.
+pub trait ResidentKeys<Key> {
+    /// The keys the cache holds, in any order.
+    fn resident_keys(&self) -> Vec<Key>;
+}
@@ -1,3 +1,7 @@
+// Under loom, the mutex is loom's, so that loom can explore every order threads take it in.
+#[cfg(loom)]
+use loom::sync::Mutex;
+#[cfg(not(loom))]
 use std::sync::Mutex;
 
 use crate::SizeLimitedCache;
@@ -0,0 +1,281 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::Arc,
+};
+
+use cache::{RemovableCache, SizeLimitedCache};
+
+/// The next-use index of a request whose key is never requested again.
+pub const NEVER: usize = usize::MAX;
+
+/// For each request, the index of the next request for the same key, or `NEVER`.
+///
+/// This is a single backward pass over the trace. Compute it once and share it between
+/// BeladyCaches of different capacities.
+pub fn next_use_indices<Key>(requests: impl IntoIterator<Item = Key>) -> Vec<usize>
+where
+    Key: Eq + Hash,
+{
+    let requests: Vec<Key> = requests.into_iter().collect();
+    let mut next_uses = vec![NEVER; requests.len()];
+    let mut seen_at: HashMap<&Key, usize> = HashMap::new();
+    for (index, key) in requests.iter().enumerate().rev() {
+        if let Some(next) = seen_at.insert(key, index) {
+            next_uses[index] = next;
+        }
+    }
+    next_uses
+}
+
+/// Belady's MIN: the offline optimal policy, which evicts the key that will be used furthest in
+/// the future.
+///
+/// No real cache can know the future, so this only works in a simulator that replays a trace it
+/// has already read. It gives the best hit ratio any policy could possibly get on that trace at
+/// a given capacity, which tells you how much room a real policy has left to improve.
+///
+/// The cache follows along with the trace: every `get` is the next request in the trace, and so
+/// is every `set`, except a `set` of the key whose `get` just missed, which is the same request
+/// filling the cache. The incoming key is always inserted, like every other SizeLimitedCache.
+pub struct BeladyCache<Key, Value> {
+    cache: HashMap<Key, BeladyEntry<Value>>,
+    /// Resident keys ordered by their next use. The last one is the next victim.
+    by_next_use: BTreeMap<(usize, u64), Key>,
+    next_uses: Arc<[usize]>,
+    /// The index of the next request in the trace.
+    position: usize,
+    /// The key and request index of a `get` that missed, which the next `set` may fill.
+    pending_miss: Option<(Key, usize)>,
+    capacity: usize,
+    next_tick: u64,
+}
+
+struct BeladyEntry<Value> {
+    value: Value,
+    next_use: (usize, u64),
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for BeladyCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        let value = self.lookup(key);
+        self.debug_validate();
+        value
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.insert(key, value);
+        self.debug_validate();
+    }
+}
+
+/// Removing a key isn't a request in the trace, so it doesn't move the cache along the trace.
+impl<Key, Value> RemovableCache<Key, Value> for BeladyCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.remove(key).map(|entry| {
+            self.by_next_use.remove(&entry.next_use);
+            entry.value
+        });
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.cache.clear();
+        self.by_next_use.clear();
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> BeladyCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    /// Create a new BeladyCache holding up to `capacity` items, for the trace whose next-use
+    /// indices are `next_uses`. See [next_use_indices].
+    pub fn new(capacity: usize, next_uses: Arc<[usize]>) -> Self {
+        Self {
+            cache: HashMap::with_capacity(capacity),
+            by_next_use: BTreeMap::new(),
+            next_uses,
+            position: 0,
+            pending_miss: None,
+            capacity,
+            next_tick: 0,
+        }
+    }
+
+    /// The number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Whether the cache holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// How many requests of the trace have been replayed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Checks that the map and the next-use order agree, and panics if they don't, that a
+    /// pending miss isn't resident, and that there are no more items than the capacity.
+    pub fn validate(&self) {
+        assert_eq!(
+            self.cache.len(),
+            self.by_next_use.len(),
+            "the map and the next-use order should hold the same number of keys"
+        );
+        for (key, entry) in &self.cache {
+            assert!(
+                self.by_next_use.get(&entry.next_use) == Some(key),
+                "the next-use order should have each key at its next use"
+            );
+        }
+        if let Some((missed, _)) = &self.pending_miss {
+            assert!(
+                !self.cache.contains_key(missed),
+                "a missed key shouldn't be resident"
+            );
+        }
+        assert!(
+            self.cache.len() <= self.capacity,
+            "the cache should hold no more than its capacity"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
+    }
+
+    /// [SizeLimitedCache::get], before validation.
+    fn lookup(&mut self, key: &Key) -> Option<Value> {
+        let request = self.next_request();
+        if !self.cache.contains_key(key) {
+            self.pending_miss = Some((key.clone(), request));
+            return None;
+        }
+        self.pending_miss = None;
+        self.reschedule(key, request);
+        self.cache.get(key).map(|entry| entry.value.clone())
+    }
+
+    /// [SizeLimitedCache::set], before validation.
+    fn insert(&mut self, key: Key, value: Value) {
+        let request = match self.pending_miss.take() {
+            Some((missed, request)) if missed == key => request,
+            _ => self.next_request(),
+        };
+
+        if let Some(entry) = self.cache.get_mut(&key) {
+            entry.value = value;
+            self.reschedule(&key, request);
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+
+        while self.capacity <= self.cache.len() {
+            let Some((_, victim)) = self.by_next_use.pop_last() else {
+                break;
+            };
+            self.cache.remove(&victim);
+        }
+
+        let next_use = self.next_use_key(request);
+        self.by_next_use.insert(next_use, key.clone());
+        self.cache.insert(key, BeladyEntry { value, next_use });
+    }
+
+    fn next_request(&mut self) -> usize {
+        let request = self.position;
+        self.position += 1;
+        request
+    }
+
+    /// Moves a resident key to the next use after `request`.
+    fn reschedule(&mut self, key: &Key, request: usize) {
+        let next_use = self.next_use_key(request);
+        if let Some(entry) = self.cache.get_mut(key) {
+            self.by_next_use.remove(&entry.next_use);
+            entry.next_use = next_use;
+            self.by_next_use.insert(next_use, key.clone());
+        }
+    }
+
+    /// Keys that are never used again all share `NEVER`, so a tick keeps their sort keys unique.
+    fn next_use_key(&mut self, request: usize) -> (usize, u64) {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        let next_use = self.next_uses.get(request).copied().unwrap_or(NEVER);
+        (next_use, tick)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::SizeLimitedCache;
+
+    use crate::{next_use_indices, BeladyCache, NEVER};
+
+    // Without a trace to look ahead in, every key looks like it's never used again.
+    cache_testkit::size_limited_cache_tests!(|capacity| BeladyCache::new(capacity, [].into()));
+    cache_testkit::removable_cache_tests!(|capacity| BeladyCache::new(capacity, [].into()));
+
+    fn misses(trace: &[u32], capacity: usize) -> usize {
+        let mut cache = BeladyCache::new(capacity, next_use_indices(trace).into());
+        trace
+            .iter()
+            .filter(|&&key| {
+                let miss = cache.get(&key).is_none();
+                if miss {
+                    cache.set(key, key);
+                }
+                miss
+            })
+            .count()
+    }
+
+    #[test]
+    fn next_uses() {
+        assert_eq!(
+            next_use_indices([1, 2, 1, 3, 2, 1]),
+            vec![2, 4, 5, NEVER, NEVER, NEVER]
+        );
+    }
+
+    #[test]
+    fn textbook_reference_string() {
+        let trace = [1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5];
+        assert_eq!(misses(&trace, 3), 7);
+        assert_eq!(misses(&trace, 4), 6);
+    }
+
+    #[test]
+    fn keeps_the_key_needed_soonest() {
+        let trace = [1, 2, 3, 1, 3, 2];
+        let mut cache = BeladyCache::new(2, next_use_indices(trace).into());
+        for key in [1, 2, 3] {
+            assert_eq!(cache.get(&key), None);
+            cache.set(key, key);
+        }
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.get(&3), Some(3));
+        assert_eq!(cache.get(&2), None, "2 was used furthest in the future");
+        assert_eq!(cache.position(), 6);
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+
+use cache::{RemovableCache, SizeLimitedCache, MAX_SIZE};
+
+/// The GreedyDual-Size-Frequency policy from Cherkasova, "Improving WWW Proxies Performance with
+/// Greedy-Dual-Size-Frequency Caching Policy".
+///
+/// Each entry has a priority of `(frequency * cost / size) + L`, and the entry with the lowest
+/// priority is evicted. `cost` is the miss penalty: how expensive the value is to recompute.
+/// `L` is the inflation value. It starts at 0 and rises to the priority of each evicted entry,
+/// so entries that were popular long ago eventually age out in favor of newer ones.
+///
+/// `capacity` is measured in the units of the weigher, which by default counts each entry as 1.
+pub struct GdsfCache<Key, Value> {
+    cache: HashMap<Key, GdsfEntry<Value>>,
+    /// Entries ordered by priority, with ties broken by insertion order.
+    priorities: BTreeMap<PriorityKey, Key>,
+    weigher: fn(&Key, &Value) -> usize,
+    inflation: f64,
+    weight: usize,
+    capacity: usize,
+    next_tick: u64,
+}
+
+struct GdsfEntry<Value> {
+    value: Value,
+    cost: f64,
+    size: usize,
+    frequency: u64,
+    priority: PriorityKey,
+}
+
+/// f64 isn't Ord, because of NaN. Priorities are never negative or NaN, and the bit patterns of
+/// non-negative floats sort in the same order as their values, so the bits work as a sort key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct PriorityKey {
+    priority_bits: u64,
+    tick: u64,
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for GdsfCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.get(key).map(|entry| entry.value.clone());
+        if value.is_some() {
+            self.touch(key);
+        }
+        self.debug_validate();
+        value
+    }
+
+    /// Sets a value with a cost of 1, which makes GDSF a frequency- and size-aware LRU.
+    fn set(&mut self, key: Key, value: Value) {
+        self.set_with_cost(key, value, 1.0);
+    }
+}
+
+/// Removing a key isn't an eviction, so it leaves the inflation value where it was. Clearing the
+/// cache resets it, since there are no entries left for new ones to age against.
+impl<Key, Value> RemovableCache<Key, Value> for GdsfCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.remove(key).map(|entry| {
+            self.priorities.remove(&entry.priority);
+            self.weight -= entry.size;
+            entry.value
+        });
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.cache.clear();
+        self.priorities.clear();
+        self.inflation = 0.0;
+        self.weight = 0;
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> Default for GdsfCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new(MAX_SIZE)
+    }
+}
+
+impl<Key, Value> GdsfCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    /// Create a new GdsfCache holding up to `capacity` items.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_weigher(capacity, |_, _| 1)
+    }
+
+    /// Create a new GdsfCache holding up to `capacity` total weight, where `weigher` gives the
+    /// size of each entry, e.g. its length in bytes.
+    pub fn with_weigher(capacity: usize, weigher: fn(&Key, &Value) -> usize) -> Self {
+        Self {
+            cache: HashMap::new(),
+            priorities: BTreeMap::new(),
+            weigher,
+            inflation: 0.0,
+            weight: 0,
+            capacity,
+            next_tick: 0,
+        }
+    }
+
+    /// Sets a value in the cache, recording `cost` as the penalty for missing it.
+    ///
+    /// `cost` must be finite and not negative. A value heavier than the whole cache is not stored.
+    pub fn set_with_cost(&mut self, key: Key, value: Value, cost: f64) {
+        assert!(
+            cost.is_finite() && 0.0 <= cost,
+            "cost must be finite and not negative, not {cost}"
+        );
+        self.insert(key, value, cost);
+        self.debug_validate();
+    }
+
+    /// [GdsfCache::set_with_cost], before validation.
+    fn insert(&mut self, key: Key, value: Value, cost: f64) {
+        let size = (self.weigher)(&key, &value).max(1);
+
+        if let Some(entry) = self.cache.get_mut(&key) {
+            self.weight = self.weight - entry.size + size;
+            entry.value = value;
+            entry.cost = cost;
+            entry.size = size;
+            self.touch(&key);
+            self.evict_while_over_capacity(0);
+            return;
+        }
+        if self.capacity < size {
+            return;
+        }
+
+        self.evict_while_over_capacity(size);
+
+        // A new entry has a frequency of 1.
+        let priority = self.next_priority(cost / size as f64);
+        self.priorities.insert(priority, key.clone());
+        self.weight += size;
+        self.cache.insert(
+            key,
+            GdsfEntry {
+                value,
+                cost,
+                size,
+                frequency: 1,
+                priority,
+            },
+        );
+    }
+
+    /// The current inflation value `L`: the priority of the most recently evicted entry.
+    pub fn inflation(&self) -> f64 {
+        self.inflation
+    }
+
+    /// The total weight of the entries in the cache.
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// The number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Whether the cache holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Checks that the map and the priority order agree, and panics if they don't, and that
+    /// the total weight adds up and fits in the capacity.
+    pub fn validate(&self) {
+        assert_eq!(
+            self.cache.len(),
+            self.priorities.len(),
+            "the map and the priority order should hold the same number of keys"
+        );
+        for (key, entry) in &self.cache {
+            assert!(
+                self.priorities.get(&entry.priority) == Some(key),
+                "the priority order should have each key at its priority"
+            );
+            assert!(0 < entry.size, "every entry should weigh something");
+        }
+        let weight: usize = self.cache.values().map(|entry| entry.size).sum();
+        assert_eq!(weight, self.weight, "the total weight should add up");
+        assert!(
+            self.weight <= self.capacity,
+            "the cache should weigh no more than its capacity"
+        );
+        assert!(
+            self.inflation.is_finite() && 0.0 <= self.inflation,
+            "the inflation value should be finite and not negative"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
+    }
+
+    /// Counts a reference to a resident key and raises its priority.
+    fn touch(&mut self, key: &Key) {
+        let Some(entry) = self.cache.get(key) else {
+            return;
+        };
+        let old_priority = entry.priority;
+        let frequency = entry.frequency + 1;
+        let benefit = frequency as f64 * entry.cost / entry.size as f64;
+
+        let priority = self.next_priority(benefit);
+        if let Some(entry) = self.cache.get_mut(key) {
+            entry.frequency = frequency;
+            entry.priority = priority;
+        }
+        if let Some(key) = self.priorities.remove(&old_priority) {
+            self.priorities.insert(priority, key);
+        }
+    }
+
+    /// Evicts the lowest priority entries until `incoming` more weight fits in the cache.
+    fn evict_while_over_capacity(&mut self, incoming: usize) {
+        while self.capacity < self.weight + incoming {
+            let Some((priority, key)) = self.priorities.pop_first() else {
+                return;
+            };
+            if let Some(entry) = self.cache.remove(&key) {
+                self.weight -= entry.size;
+            }
+            self.inflation = f64::from_bits(priority.priority_bits);
+        }
+    }
+
+    fn next_priority(&mut self, benefit: f64) -> PriorityKey {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        PriorityKey {
+            priority_bits: (self.inflation + benefit).to_bits(),
+            tick,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::SizeLimitedCache;
+
+    use crate::GdsfCache;
+
+    cache_testkit::size_limited_cache_tests!(GdsfCache::new);
+    cache_testkit::removable_cache_tests!(GdsfCache::new);
+
+    #[test]
+    fn expensive_entries_outlive_cheap_ones() {
+        let mut cache = GdsfCache::new(2);
+        cache.set_with_cost("slow", 1, 100.0);
+        for key in ["a", "b", "c", "d"] {
+            cache.set_with_cost(key, 2, 1.0);
+        }
+        assert_eq!(cache.get(&"slow"), Some(1));
+        assert_eq!(cache.get(&"d"), Some(2));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.inflation(), 3.0, "c was evicted at priority 3");
+    }
+
+    #[test]
+    fn inflation_ages_out_once_popular_entries() {
+        let mut cache = GdsfCache::new(2);
+        cache.set(100, 100);
+        for _ in 0..3 {
+            cache.get(&100);
+        }
+        // 100 has priority 4. Each new key enters at L + 1, and each eviction raises L.
+        for key in 0..10 {
+            cache.set(key, key);
+        }
+        assert_eq!(cache.get(&100), None);
+        assert!(4.0 <= cache.inflation());
+    }
+
+    #[test]
+    fn weigher_limits_total_size() {
+        let mut cache = GdsfCache::with_weigher(10, |_: &u32, value: &String| value.len());
+        cache.set(1, "12345".to_string());
+        cache.set(2, "1234".to_string());
+        cache.set(3, "12".to_string());
+        assert_eq!(cache.weight(), 6);
+        assert_eq!(
+            cache.get(&1),
+            None,
+            "the biggest entry has the lowest priority"
+        );
+
+        cache.set(4, "this value is too big".to_string());
+        assert_eq!(cache.get(&4), None);
+        assert_eq!(cache.weight(), 6);
+    }
+}
@@ -0,0 +1,19 @@
+//! Eviction policies to compare the SIEVE caches against.
+//!
+//! # Checking invariants
+//! Each cache's `validate` method panics if its bookkeeping is inconsistent. Debug builds call it
+//! after every operation when the `validate` feature is on, as the fuzz targets turn it on, and
+//! this crate's own tests always do. Release builds never check.
+
+mod belady_cache;
+mod gdsf_cache;
+mod lirs_cache;
+pub(crate) mod recency_list;
+mod sampled_cache;
+mod two_q_cache;
+
+pub use belady_cache::{next_use_indices, BeladyCache, NEVER};
+pub use gdsf_cache::GdsfCache;
+pub use lirs_cache::LirsCache;
+pub use sampled_cache::{SampleScoring, SampledCache};
+pub use two_q_cache::TwoQCache;
@@ -0,0 +1,464 @@
+use std::{collections::HashMap, hash::Hash};
+
+use cache::{RemovableCache, SizeLimitedCache, MAX_SIZE};
+
+use crate::recency_list::RecencyList;
+
+/// The LIRS policy from Jiang and Zhang, "LIRS: An Efficient Low Inter-reference Recency Set
+/// Replacement Policy to Improve Buffer Cache Performance".
+///
+/// Keys are ranked by inter-reference recency: how many other distinct keys were touched between
+/// their last two references. Keys with low recency (LIR) hold most of the cache. Keys with high
+/// recency (HIR) share a small slice of the cache, and they are the only keys ever evicted. A
+/// scan is made entirely of HIR keys, so it cannot push out the LIR set.
+pub struct LirsCache<Key, Value> {
+    entries: HashMap<Key, LirsEntry<Value>>,
+    /// The LIRS stack S. It holds LIR keys, plus HIR keys that are more recent than the oldest
+    /// LIR key. The oldest key in the stack is always LIR.
+    stack: RecencyList<Key>,
+    /// The LIRS queue Q of resident HIR keys. These are the eviction candidates.
+    hir_queue: RecencyList<Key>,
+    /// Non-resident HIR keys still in the stack, oldest first, so their number can be bounded.
+    non_resident: RecencyList<Key>,
+    lir_count: usize,
+    resident_count: usize,
+    capacity: usize,
+    hir_capacity: usize,
+    non_resident_capacity: usize,
+}
+
+struct LirsEntry<Value> {
+    /// None for non-resident HIR keys, which are remembered only for their recency.
+    value: Option<Value>,
+    is_lir: bool,
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for LirsCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        let value = self.lookup(key);
+        self.debug_validate();
+        value
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.insert(key, value);
+        self.debug_validate();
+    }
+}
+
+/// A removed key is forgotten entirely, along with its recency, even if it was only remembered
+/// as a non-resident HIR key.
+impl<Key, Value> RemovableCache<Key, Value> for LirsCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.forget(key);
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.stack = RecencyList::new();
+        self.hir_queue = RecencyList::new();
+        self.non_resident = RecencyList::new();
+        self.lir_count = 0;
+        self.resident_count = 0;
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> Default for LirsCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new(MAX_SIZE)
+    }
+}
+
+impl<Key, Value> LirsCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    /// Create a new LirsCache holding up to `capacity` items, with the paper's recommended
+    /// tuning: 1% of the capacity (at least 1 item) for resident HIR keys. Up to `capacity`
+    /// non-resident HIR keys are remembered.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_parameters(capacity, (capacity / 100).max(1), capacity)
+    }
+
+    /// Create a new LirsCache with an explicit share of the capacity for resident HIR keys, and
+    /// an explicit bound on the number of non-resident HIR keys remembered in the stack.
+    pub fn with_parameters(
+        capacity: usize,
+        hir_capacity: usize,
+        non_resident_capacity: usize,
+    ) -> Self {
+        Self {
+            entries: HashMap::with_capacity(capacity),
+            stack: RecencyList::new(),
+            hir_queue: RecencyList::new(),
+            non_resident: RecencyList::new(),
+            lir_count: 0,
+            resident_count: 0,
+            capacity,
+            hir_capacity: hir_capacity.min(capacity),
+            non_resident_capacity,
+        }
+    }
+
+    /// The number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.resident_count
+    }
+
+    /// Whether the cache holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.resident_count == 0
+    }
+
+    /// The share of the capacity for resident HIR keys.
+    pub fn hir_capacity(&self) -> usize {
+        self.hir_capacity
+    }
+
+    /// The share of the capacity for LIR keys.
+    pub fn lir_capacity(&self) -> usize {
+        self.capacity - self.hir_capacity
+    }
+
+    /// The most non-resident HIR keys that are remembered in the stack.
+    pub fn non_resident_capacity(&self) -> usize {
+        self.non_resident_capacity
+    }
+
+    /// Checks that the entries, the stack and the queues agree, and panics if they don't: the
+    /// LIR and resident counts are right, the HIR queue holds exactly the resident HIR keys,
+    /// every LIR and non-resident key is in the stack, the stack ends in an LIR key, and nothing
+    /// is over its capacity.
+    pub fn validate(&self) {
+        self.stack.validate();
+        self.hir_queue.validate();
+        self.non_resident.validate();
+        let lir = self.entries.values().filter(|entry| entry.is_lir).count();
+        let resident = self
+            .entries
+            .values()
+            .filter(|entry| entry.value.is_some())
+            .count();
+        assert_eq!(lir, self.lir_count, "the LIR count should be right");
+        assert_eq!(
+            resident, self.resident_count,
+            "the resident count should be right"
+        );
+        assert_eq!(
+            self.hir_queue.len(),
+            resident - lir,
+            "the HIR queue should hold every resident HIR key"
+        );
+        assert_eq!(
+            self.non_resident.len(),
+            self.entries.len() - resident,
+            "every non-resident key should be counted"
+        );
+        for (key, entry) in &self.entries {
+            match (entry.is_lir, entry.value.is_some()) {
+                (true, true) => assert!(self.stack.contains(key), "LIR keys should be stacked"),
+                (true, false) => panic!("LIR keys should be resident"),
+                (false, true) => {
+                    assert!(
+                        self.hir_queue.contains(key),
+                        "resident HIR keys should be queued"
+                    )
+                }
+                (false, false) => {
+                    assert!(
+                        self.stack.contains(key),
+                        "non-resident keys should be stacked"
+                    );
+                    assert!(
+                        self.non_resident.contains(key),
+                        "non-resident keys should be counted"
+                    );
+                }
+            }
+        }
+        assert!(
+            self.stack.iter().all(|key| self.entries.contains_key(key)),
+            "every stacked key should have an entry"
+        );
+        // With no LIR share of the capacity, as at capacity 1, the stack is all HIR keys.
+        if let Some(bottom) = self.stack.front().filter(|_| 0 < self.lir_count) {
+            assert!(
+                self.entries[bottom].is_lir,
+                "the oldest key in the stack should be LIR"
+            );
+        }
+        assert!(
+            self.lir_count <= self.lir_capacity(),
+            "there should be no more LIR keys than the LIR share of the capacity"
+        );
+        assert!(
+            self.non_resident.len() <= self.non_resident_capacity,
+            "no more non-resident keys than their bound should be remembered"
+        );
+        assert!(
+            self.resident_count <= self.capacity,
+            "the cache should hold no more than its capacity"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
+    }
+
+    /// [SizeLimitedCache::get], before validation.
+    fn lookup(&mut self, key: &Key) -> Option<Value> {
+        let value = self.entries.get(key)?.value.clone()?;
+        self.touch_resident(key);
+        Some(value)
+    }
+
+    /// [SizeLimitedCache::set], before validation.
+    fn insert(&mut self, key: Key, value: Value) {
+        if let Some(LirsEntry {
+            value: Some(resident),
+            ..
+        }) = self.entries.get_mut(&key)
+        {
+            *resident = value;
+            self.touch_resident(&key);
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.resident_count < self.capacity && self.lir_count < self.lir_capacity() {
+            // While the cache warms up, everything is LIR until the LIR slice is full.
+            if self.entries.contains_key(&key) {
+                self.forget_non_resident(&key);
+            }
+            self.stack.push_back(key.clone());
+            self.entries.insert(
+                key,
+                LirsEntry {
+                    value: Some(value),
+                    is_lir: true,
+                },
+            );
+            self.lir_count += 1;
+            self.resident_count += 1;
+            return;
+        }
+
+        self.make_room_for_one_insertion();
+        self.bound_non_resident();
+        self.resident_count += 1;
+
+        if self.entries.contains_key(&key) && self.stack.contains(&key) {
+            // A non-resident HIR key still in the stack was referenced again more recently than
+            // the oldest LIR key, so it has the lower inter-reference recency of the two.
+            self.non_resident.remove(&key);
+            self.stack.push_back(key.clone());
+            self.entries.insert(
+                key,
+                LirsEntry {
+                    value: Some(value),
+                    is_lir: true,
+                },
+            );
+            self.lir_count += 1;
+            self.demote_oldest_lir();
+        } else {
+            self.stack.push_back(key.clone());
+            self.hir_queue.push_back(key.clone());
+            self.entries.insert(
+                key,
+                LirsEntry {
+                    value: Some(value),
+                    is_lir: false,
+                },
+            );
+        }
+    }
+
+    /// Handles a reference to a resident key.
+    fn touch_resident(&mut self, key: &Key) {
+        let entry = self
+            .entries
+            .get_mut(key)
+            .expect("resident keys have entries");
+        if entry.is_lir {
+            self.stack.push_back(key.clone());
+            self.prune_stack();
+        } else if self.stack.contains(key) {
+            // Referenced again while still in the stack: its recency beats the oldest LIR key.
+            entry.is_lir = true;
+            self.lir_count += 1;
+            self.hir_queue.remove(key);
+            self.stack.push_back(key.clone());
+            self.demote_oldest_lir();
+        } else {
+            self.stack.push_back(key.clone());
+            self.hir_queue.push_back(key.clone());
+        }
+    }
+
+    /// Turns the oldest LIR key into a resident HIR key, then prunes the stack.
+    fn demote_oldest_lir(&mut self) {
+        // The stack only ends in an LIR key once it's pruned. Without pruning first, a HIR key
+        // (maybe not even resident) would be "demoted", and the counts would drift.
+        self.prune_stack();
+        if let Some(key) = self.stack.pop_front() {
+            if let Some(entry) = self.entries.get_mut(&key) {
+                debug_assert!(entry.is_lir, "the bottom of a pruned stack is LIR");
+                entry.is_lir = false;
+                self.lir_count -= 1;
+                self.hir_queue.push_back(key);
+            }
+        }
+        self.prune_stack();
+    }
+
+    /// Removes HIR keys from the bottom of the stack until the oldest key in the stack is LIR.
+    fn prune_stack(&mut self) {
+        while let Some(key) = self.stack.front() {
+            let entry = self.entries.get(key).expect("stacked keys have entries");
+            if entry.is_lir {
+                break;
+            }
+            let resident = entry.value.is_some();
+            if let Some(key) = self.stack.pop_front() {
+                if !resident {
+                    self.forget_non_resident(&key);
+                }
+            }
+        }
+    }
+
+    /// Evicts resident HIR keys until one more key can be inserted.
+    fn make_room_for_one_insertion(&mut self) {
+        while 0 < self.resident_count && self.capacity <= self.resident_count {
+            let victim = match self.hir_queue.pop_front() {
+                Some(key) => key,
+                None => {
+                    // No resident HIR keys, which only happens when the HIR slice is empty.
+                    // Demote the oldest LIR key so there is something to evict.
+                    self.demote_oldest_lir();
+                    continue;
+                }
+            };
+            self.resident_count -= 1;
+            if self.stack.contains(&victim) {
+                if let Some(entry) = self.entries.get_mut(&victim) {
+                    entry.value = None;
+                }
+                self.non_resident.push_back(victim);
+            } else {
+                self.entries.remove(&victim);
+            }
+        }
+    }
+
+    /// Keeps the number of non-resident HIR keys remembered in the stack within its bound.
+    fn bound_non_resident(&mut self) {
+        while self.non_resident_capacity < self.non_resident.len() {
+            if let Some(key) = self.non_resident.pop_front() {
+                self.stack.remove(&key);
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// [RemovableCache::remove], before validation.
+    fn forget(&mut self, key: &Key) -> Option<Value> {
+        let entry = self.entries.remove(key)?;
+        self.stack.remove(key);
+        self.hir_queue.remove(key);
+        self.non_resident.remove(key);
+        if entry.is_lir {
+            self.lir_count -= 1;
+            // The removed key may have been the oldest in the stack.
+            self.prune_stack();
+        }
+        if entry.value.is_some() {
+            self.resident_count -= 1;
+        }
+        entry.value
+    }
+
+    fn forget_non_resident(&mut self, key: &Key) {
+        self.non_resident.remove(key);
+        self.stack.remove(key);
+        self.entries.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::SizeLimitedCache;
+
+    use crate::LirsCache;
+
+    cache_testkit::size_limited_cache_tests!(LirsCache::new);
+    cache_testkit::removable_cache_tests!(LirsCache::new);
+
+    #[test]
+    fn capacity_is_respected() {
+        let mut cache = LirsCache::new(10);
+        for key in 0..1000 {
+            cache.set(key % 37, key);
+            cache.get(&(key % 13));
+        }
+        assert_eq!(cache.len(), 10);
+        let resident = (0..37).filter(|key| cache.get(key).is_some()).count();
+        assert_eq!(resident, 10);
+    }
+
+    #[test]
+    fn scan_does_not_flush_the_lir_set() {
+        let mut cache = LirsCache::with_parameters(4, 1, 4);
+        for key in 0..3 {
+            cache.set(key, key);
+        }
+        for key in 1000..2000 {
+            cache.set(key, key);
+            for hot in 0..3 {
+                assert_eq!(
+                    cache.get(&hot),
+                    Some(hot),
+                    "hot key {hot} was evicted by {key}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn re_referenced_hir_key_becomes_lir() {
+        let mut cache = LirsCache::with_parameters(3, 1, 3);
+        cache.set('a', 'a');
+        cache.set('b', 'b');
+        cache.set('c', 'c');
+        assert!(!cache.entries[&'c'].is_lir, "the LIR slice was full");
+
+        cache.get(&'c');
+        assert!(
+            cache.entries[&'c'].is_lir,
+            "c was referenced within the stack"
+        );
+        assert!(!cache.entries[&'a'].is_lir, "a was the oldest LIR key");
+        assert_eq!(cache.lir_count, 2);
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+};
+
+/// An ordered list of keys, oldest first, that supports removing or moving any key.
+///
+/// The usual way to build this is a hash map pointing into a doubly linked list. Linked lists
+/// are famously awkward to write in safe rust, so this uses a BTreeMap keyed by a
+/// monotonically increasing tick instead. It's O(log n) rather than O(1), but it's short and
+/// obviously correct, which is what we want from reference implementations of policies.
+pub struct RecencyList<Key> {
+    ticks: HashMap<Key, u64>,
+    order: BTreeMap<u64, Key>,
+    next_tick: u64,
+}
+
+impl<Key> RecencyList<Key>
+where
+    Key: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            ticks: HashMap::new(),
+            order: BTreeMap::new(),
+            next_tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    pub fn contains(&self, key: &Key) -> bool {
+        self.ticks.contains_key(key)
+    }
+
+    /// The oldest key in the list.
+    pub fn front(&self) -> Option<&Key> {
+        self.order.values().next()
+    }
+
+    /// Adds a key as the newest in the list, or moves it there if it is already present.
+    pub fn push_back(&mut self, key: Key) {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        if let Some(old_tick) = self.ticks.insert(key.clone(), tick) {
+            self.order.remove(&old_tick);
+        }
+        self.order.insert(tick, key);
+    }
+
+    /// Removes and returns the oldest key in the list.
+    pub fn pop_front(&mut self) -> Option<Key> {
+        let (_, key) = self.order.pop_first()?;
+        self.ticks.remove(&key);
+        Some(key)
+    }
+
+    /// Iterates over the keys in the list, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &Key> {
+        self.order.values()
+    }
+
+    /// Checks that the key index and the order agree, and panics if they don't.
+    pub fn validate(&self) {
+        assert_eq!(
+            self.ticks.len(),
+            self.order.len(),
+            "a recency list's index and order should hold the same number of keys"
+        );
+        for (tick, key) in &self.order {
+            assert_eq!(
+                self.ticks.get(key),
+                Some(tick),
+                "a recency list's index should point at each key's place in the order"
+            );
+            assert!(*tick < self.next_tick, "ticks should come from the past");
+        }
+    }
+
+    /// Removes a key from anywhere in the list. Returns whether it was present.
+    pub fn remove(&mut self, key: &Key) -> bool {
+        match self.ticks.remove(key) {
+            Some(tick) => {
+                self.order.remove(&tick);
+                true
+            }
+            None => false,
+        }
+    }
+}
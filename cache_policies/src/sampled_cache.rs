@@ -0,0 +1,349 @@
+use std::{collections::HashMap, hash::Hash};
+
+use cache::{RemovableCache, SizeLimitedCache, MAX_SIZE};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// How a SampledCache picks its victim from the sampled entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleScoring {
+    /// Evict a uniformly random entry. The sample size makes no difference.
+    Random,
+    /// Evict the sampled entry with the oldest access, approximating LRU.
+    ApproximateLru,
+    /// Evict the sampled entry with the fewest accesses, approximating LFU.
+    ApproximateLfu,
+}
+
+/// Redis-style sampled eviction.
+///
+/// Instead of keeping the entries in an eviction order, which costs memory and synchronization
+/// on every access, each entry only records its last access time and its access count. To evict,
+/// pick `sample_size` random entries and evict the worst of them. With a sample size of around
+/// 5 to 10 this gets surprisingly close to the exact policy it approximates.
+pub struct SampledCache<Key, Value> {
+    /// Maps each key to its position in `entries`.
+    positions: HashMap<Key, usize>,
+    /// A dense list, so that a random entry is just a random index.
+    entries: Vec<SampledEntry<Key, Value>>,
+    random: StdRng,
+    clock: u64,
+    capacity: usize,
+    sample_size: usize,
+    scoring: SampleScoring,
+}
+
+struct SampledEntry<Key, Value> {
+    key: Key,
+    value: Value,
+    last_access: u64,
+    access_count: u32,
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for SampledCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        let value = self.lookup(key);
+        self.debug_validate();
+        value
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.insert(key, value);
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for SampledCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self
+            .positions
+            .get(key)
+            .copied()
+            .map(|position| self.remove_at(position).value);
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.positions.clear();
+        self.entries.clear();
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> Default for SampledCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new(MAX_SIZE, 5, SampleScoring::ApproximateLru)
+    }
+}
+
+impl<Key, Value> SampledCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    /// Create a new SampledCache holding up to `capacity` items, which looks at `sample_size`
+    /// random entries for each eviction.
+    ///
+    /// The random number generator has a fixed seed, so a simulation gives the same result every
+    /// time it runs.
+    pub fn new(capacity: usize, sample_size: usize, scoring: SampleScoring) -> Self {
+        Self::with_seed(capacity, sample_size, scoring, 37)
+    }
+
+    /// Create a new SampledCache whose random number generator starts from `seed`.
+    pub fn with_seed(
+        capacity: usize,
+        sample_size: usize,
+        scoring: SampleScoring,
+        seed: u64,
+    ) -> Self {
+        Self {
+            positions: HashMap::with_capacity(capacity),
+            entries: Vec::with_capacity(capacity),
+            random: StdRng::seed_from_u64(seed),
+            clock: 0,
+            capacity,
+            sample_size: sample_size.max(1),
+            scoring,
+        }
+    }
+
+    /// The number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How many random entries are compared for each eviction.
+    pub fn sample_size(&self) -> usize {
+        self.sample_size
+    }
+
+    /// How the victim is picked from the sample.
+    pub fn scoring(&self) -> SampleScoring {
+        self.scoring
+    }
+
+    /// Checks that the key index and the entries agree, and panics if they don't, and that
+    /// there are no more entries than the capacity.
+    pub fn validate(&self) {
+        assert_eq!(
+            self.positions.len(),
+            self.entries.len(),
+            "the index and the entries should hold the same number of keys"
+        );
+        for (position, entry) in self.entries.iter().enumerate() {
+            assert_eq!(
+                self.positions.get(&entry.key),
+                Some(&position),
+                "the index should point at each key's entry"
+            );
+            assert!(
+                entry.last_access <= self.clock,
+                "accesses should be in the past"
+            );
+        }
+        assert!(
+            self.entries.len() <= self.capacity,
+            "the cache should hold no more than its capacity"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
+    }
+
+    /// [SizeLimitedCache::get], before validation.
+    fn lookup(&mut self, key: &Key) -> Option<Value> {
+        let position = *self.positions.get(key)?;
+        let now = self.tick();
+        let entry = &mut self.entries[position];
+        entry.last_access = now;
+        entry.access_count = entry.access_count.saturating_add(1);
+        Some(entry.value.clone())
+    }
+
+    /// [SizeLimitedCache::set], before validation.
+    fn insert(&mut self, key: Key, value: Value) {
+        let now = self.tick();
+        if let Some(&position) = self.positions.get(&key) {
+            let entry = &mut self.entries[position];
+            entry.value = value;
+            entry.last_access = now;
+            entry.access_count = entry.access_count.saturating_add(1);
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+
+        while self.capacity <= self.entries.len() {
+            self.evict_one();
+        }
+
+        self.positions.insert(key.clone(), self.entries.len());
+        self.entries.push(SampledEntry {
+            key,
+            value,
+            last_access: now,
+            access_count: 1,
+        });
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// Samples entries and evicts the worst one.
+    fn evict_one(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let samples = match self.scoring {
+            SampleScoring::Random => 1,
+            SampleScoring::ApproximateLru | SampleScoring::ApproximateLfu => self.sample_size,
+        };
+        let victim = (0..samples)
+            .map(|_| self.random.gen_range(0..self.entries.len()))
+            .min_by_key(|&position| {
+                let entry = &self.entries[position];
+                match self.scoring {
+                    SampleScoring::Random | SampleScoring::ApproximateLru => (entry.last_access, 0),
+                    // Ties go to the older entry.
+                    SampleScoring::ApproximateLfu => (entry.access_count as u64, entry.last_access),
+                }
+            })
+            .unwrap_or(0);
+        self.remove_at(victim);
+    }
+
+    /// Removes the entry at `position` and returns it.
+    fn remove_at(&mut self, position: usize) -> SampledEntry<Key, Value> {
+        // swap_remove is O(1): it moves the last entry into the removed entry's place.
+        let removed = self.entries.swap_remove(position);
+        self.positions.remove(&removed.key);
+        if let Some(moved) = self.entries.get(position) {
+            self.positions.insert(moved.key.clone(), position);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::SizeLimitedCache;
+
+    use crate::{SampleScoring, SampledCache};
+
+    mod random {
+        use super::*;
+
+        cache_testkit::size_limited_cache_tests!(|capacity| SampledCache::new(
+            capacity,
+            1,
+            SampleScoring::Random
+        ));
+        cache_testkit::removable_cache_tests!(|capacity| SampledCache::new(
+            capacity,
+            1,
+            SampleScoring::Random
+        ));
+    }
+
+    mod approximate_lru {
+        use super::*;
+
+        cache_testkit::size_limited_cache_tests!(|capacity| SampledCache::new(
+            capacity,
+            5,
+            SampleScoring::ApproximateLru
+        ));
+        cache_testkit::removable_cache_tests!(|capacity| SampledCache::new(
+            capacity,
+            5,
+            SampleScoring::ApproximateLru
+        ));
+    }
+
+    mod approximate_lfu {
+        use super::*;
+
+        cache_testkit::size_limited_cache_tests!(|capacity| SampledCache::new(
+            capacity,
+            5,
+            SampleScoring::ApproximateLfu
+        ));
+        cache_testkit::removable_cache_tests!(|capacity| SampledCache::new(
+            capacity,
+            5,
+            SampleScoring::ApproximateLfu
+        ));
+    }
+
+    #[test]
+    fn capacity_is_respected() {
+        for scoring in [
+            SampleScoring::Random,
+            SampleScoring::ApproximateLru,
+            SampleScoring::ApproximateLfu,
+        ] {
+            let mut cache = SampledCache::new(10, 5, scoring);
+            for key in 0..100 {
+                cache.set(key, key);
+                assert_eq!(
+                    cache.get(&key),
+                    Some(key),
+                    "{scoring:?} keeps the newest key"
+                );
+            }
+            assert_eq!(cache.len(), 10);
+            assert_eq!((0..100).filter(|key| cache.get(key).is_some()).count(), 10);
+        }
+    }
+
+    #[test]
+    fn sampling_everything_is_exact_lru() {
+        // With replacement, 64 samples of 4 entries almost surely see every entry.
+        let mut cache = SampledCache::new(4, 64, SampleScoring::ApproximateLru);
+        for key in 0..4 {
+            cache.set(key, key);
+        }
+        cache.get(&0);
+        cache.set(4, 4);
+        assert_eq!(cache.get(&1), None, "1 was least recently used");
+        assert_eq!(cache.get(&0), Some(0));
+    }
+
+    #[test]
+    fn sampling_everything_is_exact_lfu() {
+        let mut cache = SampledCache::new(4, 64, SampleScoring::ApproximateLfu);
+        for key in 0..4 {
+            cache.set(key, key);
+            for _ in 0..(4 - key) {
+                cache.get(&key);
+            }
+        }
+        cache.set(4, 4);
+        assert_eq!(cache.get(&3), None, "3 was least frequently used");
+        assert_eq!(cache.len(), 4);
+    }
+}
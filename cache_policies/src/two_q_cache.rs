@@ -0,0 +1,277 @@
+use std::{collections::HashMap, hash::Hash};
+
+use cache::{RemovableCache, SizeLimitedCache, MAX_SIZE};
+
+use crate::recency_list::RecencyList;
+
+/// The full 2Q policy from Johnson and Shasha, "2Q: A Low Overhead High Performance Buffer
+/// Management Replacement Algorithm".
+///
+/// New keys go into a small FIFO, `A1in`. Keys evicted from `A1in` are remembered (without their
+/// values) in a ghost FIFO, `A1out`. Only a key that is set again while it is remembered in `A1out`
+/// is promoted into the main LRU, `Am`. A one-pass scan therefore churns through `A1in` and
+/// `A1out` without ever disturbing the hot set in `Am`.
+pub struct TwoQCache<Key, Value> {
+    cache: HashMap<Key, Value>,
+    a1_in: RecencyList<Key>,
+    a1_out: RecencyList<Key>,
+    am: RecencyList<Key>,
+    capacity: usize,
+    a1_in_capacity: usize,
+    a1_out_capacity: usize,
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for TwoQCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        let value = self.lookup(key);
+        self.debug_validate();
+        value
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.insert(key, value);
+        self.debug_validate();
+    }
+}
+
+/// A removed key is forgotten entirely: if it was remembered in `A1out`, it isn't any more, so
+/// setting it again starts it over in `A1in`.
+impl<Key, Value> RemovableCache<Key, Value> for TwoQCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.remove(key);
+        self.a1_in.remove(key);
+        self.a1_out.remove(key);
+        self.am.remove(key);
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.cache.clear();
+        self.a1_in = RecencyList::new();
+        self.a1_out = RecencyList::new();
+        self.am = RecencyList::new();
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> Default for TwoQCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new(MAX_SIZE)
+    }
+}
+
+impl<Key, Value> TwoQCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    /// Create a new TwoQCache holding up to `capacity` items, with the paper's recommended
+    /// tuning: `A1in` holds 25% of the capacity and `A1out` remembers 50% of the capacity.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_queue_sizes(capacity, capacity / 4, capacity / 2)
+    }
+
+    /// Create a new TwoQCache with explicit sizes for the `A1in` and `A1out` queues.
+    ///
+    /// `a1_in_capacity` counts against `capacity`, while `a1_out_capacity` is extra memory for
+    /// keys only.
+    pub fn with_queue_sizes(
+        capacity: usize,
+        a1_in_capacity: usize,
+        a1_out_capacity: usize,
+    ) -> Self {
+        Self {
+            cache: HashMap::with_capacity(capacity),
+            a1_in: RecencyList::new(),
+            a1_out: RecencyList::new(),
+            am: RecencyList::new(),
+            capacity,
+            a1_in_capacity: a1_in_capacity.min(capacity),
+            a1_out_capacity,
+        }
+    }
+
+    /// The number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Whether the cache holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// The size of the resident FIFO for newly inserted keys.
+    pub fn a1_in_capacity(&self) -> usize {
+        self.a1_in_capacity
+    }
+
+    /// The number of ghost keys remembered after they leave `A1in`.
+    pub fn a1_out_capacity(&self) -> usize {
+        self.a1_out_capacity
+    }
+
+    /// Checks that the map and the queues agree, and panics if they don't: every resident key
+    /// is in exactly one of `A1in` and `Am`, ghost keys in `A1out` aren't resident, and neither
+    /// the cache nor `A1out` is over its capacity.
+    pub fn validate(&self) {
+        self.a1_in.validate();
+        self.a1_out.validate();
+        self.am.validate();
+        assert_eq!(
+            self.cache.len(),
+            self.a1_in.len() + self.am.len(),
+            "every resident key should be in A1in or Am"
+        );
+        for key in self.a1_in.iter() {
+            assert!(
+                self.cache.contains_key(key),
+                "keys in A1in should be resident"
+            );
+            assert!(
+                !self.am.contains(key),
+                "no key should be in both A1in and Am"
+            );
+        }
+        for key in self.am.iter() {
+            assert!(
+                self.cache.contains_key(key),
+                "keys in Am should be resident"
+            );
+        }
+        for key in self.a1_out.iter() {
+            assert!(
+                !self.cache.contains_key(key),
+                "ghost keys in A1out shouldn't be resident"
+            );
+        }
+        assert!(
+            self.a1_out.len() <= self.a1_out_capacity,
+            "A1out should remember no more than its capacity"
+        );
+        assert!(
+            self.cache.len() <= self.capacity,
+            "the cache should hold no more than its capacity"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
+    }
+
+    /// [SizeLimitedCache::get], before validation.
+    fn lookup(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.get(key)?.clone();
+        // Hits in A1in are deliberately ignored: a key that is read a few times in quick
+        // succession and then never again should not be promoted.
+        if self.am.contains(key) {
+            self.am.push_back(key.clone());
+        }
+        Some(value)
+    }
+
+    /// [SizeLimitedCache::set], before validation.
+    fn insert(&mut self, key: Key, value: Value) {
+        if let Some(resident) = self.cache.get_mut(&key) {
+            *resident = value;
+            if self.am.contains(&key) {
+                self.am.push_back(key);
+            }
+            return;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+
+        self.make_room_for_one_insertion();
+
+        if self.a1_out.remove(&key) {
+            self.am.push_back(key.clone());
+        } else {
+            self.a1_in.push_back(key.clone());
+        }
+        self.cache.insert(key, value);
+    }
+
+    /// Makes sure the cache holds fewer than `capacity` items, so that one more item can be inserted.
+    fn make_room_for_one_insertion(&mut self) {
+        while !self.cache.is_empty() && self.capacity <= self.cache.len() {
+            if self.a1_in_capacity < self.a1_in.len() || self.am.is_empty() {
+                if let Some(key) = self.a1_in.pop_front() {
+                    self.cache.remove(&key);
+                    self.remember(key);
+                }
+            } else if let Some(key) = self.am.pop_front() {
+                self.cache.remove(&key);
+            }
+        }
+    }
+
+    /// Adds a key evicted from `A1in` to the `A1out` ghost queue.
+    fn remember(&mut self, key: Key) {
+        if self.a1_out_capacity == 0 {
+            return;
+        }
+        self.a1_out.push_back(key);
+        if self.a1_out_capacity < self.a1_out.len() {
+            self.a1_out.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::SizeLimitedCache;
+
+    use crate::TwoQCache;
+
+    cache_testkit::size_limited_cache_tests!(TwoQCache::new);
+    cache_testkit::removable_cache_tests!(TwoQCache::new);
+
+    #[test]
+    fn second_set_from_ghost_queue_is_promoted() {
+        let mut cache = TwoQCache::with_queue_sizes(4, 1, 4);
+        for key in 0..5 {
+            cache.set(key, key);
+        }
+        assert_eq!(cache.get(&0), None, "0 was the oldest key in A1in");
+
+        cache.set(0, 0);
+        assert!(cache.am.contains(&0), "0 was remembered in A1out");
+        assert_eq!(cache.len(), 4);
+    }
+
+    #[test]
+    fn scan_does_not_flush_the_hot_set() {
+        let mut cache = TwoQCache::new(8);
+        // Get 0 and 1 into Am by setting them twice.
+        for key in [0, 1, 100, 101, 102, 103, 104, 105, 106, 0, 1] {
+            cache.set(key, key);
+        }
+        assert!(cache.am.contains(&0) && cache.am.contains(&1));
+
+        for key in 1000..2000 {
+            cache.set(key, key);
+            cache.get(&0);
+            cache.get(&1);
+        }
+        assert_eq!(cache.get(&0), Some(0));
+        assert_eq!(cache.get(&1), Some(1));
+        assert_eq!(cache.len(), 8);
+    }
+}
@@ -0,0 +1,289 @@
+//! Differential tests: two caches that should behave the same, driven with the same seeded
+//! streams of gets and sets, side by side.
+//!
+//! After every operation, [compare] checks that both caches returned the same thing and hold the
+//! same keys. At the first step where they don't, it returns a [Divergence], which prints the
+//! operations leading up to it:
+//! ```text
+//! sieve and clock diverge at step 32 of seed 0 (capacity 6):
+//!        ... 12 earlier steps
+//!      12 set 8 = 78   evicts [1] holds [0, 6, 7, 8, 11, 13]
+//!      13 get 9        -> None
+//!      ...
+//!      31 get 4        -> None
+//!   >  32 set 10 = 17  sieve evicts [7] and holds [0, 2, 10, 11, 12, 13], clock evicts [13] and holds [0, 2, 7, 10, 11, 12]
+//! ```
+//! A seed always makes the same stream, so `Stream::from_seed(0)` runs that one again.
+
+use std::{fmt, ops::Range};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{sieve_model::Operation, ResidentKeys, SizeLimitedCache};
+
+/// How many operations [Stream::from_seed] makes.
+pub const STREAM_LENGTH: usize = 200;
+
+/// How many steps before a divergence its report shows.
+const TRACE_LENGTH: usize = 20;
+
+/// A named cache constructor, from capacity to cache.
+#[derive(Debug, Clone, Copy)]
+pub struct Side<New> {
+    pub name: &'static str,
+    pub new: New,
+}
+
+/// A seeded stream of gets and sets, and the capacity to run it at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stream {
+    pub seed: u64,
+    pub capacity: usize,
+    pub operations: Vec<Operation>,
+}
+
+impl Stream {
+    /// A capacity from 0 to 7, and [STREAM_LENGTH] gets and sets over a key space about twice
+    /// the capacity, so that the caches are full most of the time and keys come back after
+    /// they're evicted.
+    pub fn from_seed(seed: u64) -> Self {
+        let mut random = StdRng::seed_from_u64(seed);
+        let capacity = random.gen_range(0..8);
+        let key_space = 2 * capacity as u64 + 2;
+        let operations = (0..STREAM_LENGTH)
+            .map(|_| {
+                let key = random.gen_range(0..key_space);
+                if random.gen_bool(0.5) {
+                    Operation::Get(key)
+                } else {
+                    Operation::Set(key, random.gen_range(0..100))
+                }
+            })
+            .collect();
+        Self {
+            seed,
+            capacity,
+            operations,
+        }
+    }
+}
+
+/// What one cache did with one operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// What a get returned. Always None for a set.
+    pub got: Option<u64>,
+    /// The keys that were there before the operation and aren't after it, sorted.
+    pub evicted: Vec<u64>,
+    /// The keys the cache holds after the operation, sorted.
+    pub holds: Vec<u64>,
+}
+
+/// One operation, and what each cache did with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub operation: Operation,
+    pub left: Outcome,
+    pub right: Outcome,
+}
+
+/// The first step where two caches disagree, and every step before it.
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub names: [&'static str; 2],
+    pub seed: u64,
+    pub capacity: usize,
+    /// Every step of the stream up to and including the one where the caches disagree.
+    pub steps: Vec<Step>,
+}
+
+/// Runs the stream on a new cache from each side, and returns the first step where they return
+/// different values or hold different keys.
+pub fn compare<Left, Right>(
+    left: Side<impl Fn(usize) -> Left>,
+    right: Side<impl Fn(usize) -> Right>,
+    stream: &Stream,
+) -> Result<(), Divergence>
+where
+    Left: SizeLimitedCache<u64, u64> + ResidentKeys<u64>,
+    Right: SizeLimitedCache<u64, u64> + ResidentKeys<u64>,
+{
+    let mut left_cache = (left.new)(stream.capacity);
+    let mut right_cache = (right.new)(stream.capacity);
+    let mut steps = Vec::with_capacity(stream.operations.len());
+    for &operation in &stream.operations {
+        let step = Step {
+            operation,
+            left: apply(&mut left_cache, operation),
+            right: apply(&mut right_cache, operation),
+        };
+        let diverged = step.left != step.right;
+        steps.push(step);
+        if diverged {
+            return Err(Divergence {
+                names: [left.name, right.name],
+                seed: stream.seed,
+                capacity: stream.capacity,
+                steps,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Compares the caches on the stream from each seed, and panics with the first divergence.
+pub fn assert_same<Left, Right>(
+    left: Side<impl Fn(usize) -> Left>,
+    right: Side<impl Fn(usize) -> Right>,
+    seeds: Range<u64>,
+) where
+    Left: SizeLimitedCache<u64, u64> + ResidentKeys<u64>,
+    Right: SizeLimitedCache<u64, u64> + ResidentKeys<u64>,
+{
+    for seed in seeds {
+        if let Err(divergence) = compare(
+            Side {
+                name: left.name,
+                new: &left.new,
+            },
+            Side {
+                name: right.name,
+                new: &right.new,
+            },
+            &Stream::from_seed(seed),
+        ) {
+            panic!("{divergence}");
+        }
+    }
+}
+
+fn apply<Cache>(cache: &mut Cache, operation: Operation) -> Outcome
+where
+    Cache: SizeLimitedCache<u64, u64> + ResidentKeys<u64>,
+{
+    let before = sorted(cache.resident_keys());
+    let got = match operation {
+        Operation::Get(key) => cache.get(&key),
+        Operation::Set(key, value) => {
+            cache.set(key, value);
+            None
+        }
+    };
+    let holds = sorted(cache.resident_keys());
+    let evicted = before
+        .into_iter()
+        .filter(|key| holds.binary_search(key).is_err())
+        .collect();
+    Outcome {
+        got,
+        evicted,
+        holds,
+    }
+}
+
+fn sorted(mut keys: Vec<u64>) -> Vec<u64> {
+    keys.sort_unstable();
+    keys
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [left, right] = self.names;
+        writeln!(
+            f,
+            "{left} and {right} diverge at step {} of seed {} (capacity {}):",
+            self.steps.len() - 1,
+            self.seed,
+            self.capacity
+        )?;
+        let shown = self.steps.len().saturating_sub(TRACE_LENGTH + 1);
+        if 0 < shown {
+            writeln!(f, "       ... {shown} earlier steps")?;
+        }
+        for (index, step) in self.steps.iter().enumerate().skip(shown) {
+            let marker = if index == self.steps.len() - 1 {
+                ">"
+            } else {
+                " "
+            };
+            write!(f, "  {marker} {index:>3} ")?;
+            let operation = match step.operation {
+                Operation::Get(key) => format!("get {key}"),
+                Operation::Set(key, value) => format!("set {key} = {value}"),
+            };
+            write!(f, "{operation:<12}")?;
+            if step.left.got != step.right.got {
+                write!(
+                    f,
+                    " -> {left} {:?}, {right} {:?}",
+                    step.left.got, step.right.got
+                )?;
+            } else if let Operation::Get(_) = step.operation {
+                write!(f, " -> {:?}", step.left.got)?;
+            }
+            if step.left.evicted != step.right.evicted || step.left.holds != step.right.holds {
+                write!(
+                    f,
+                    " {left} evicts {:?} and holds {:?}, {right} evicts {:?} and holds {:?}",
+                    step.left.evicted, step.left.holds, step.right.evicted, step.right.holds
+                )?;
+            } else if let Operation::Set(..) = step.operation {
+                if !step.left.evicted.is_empty() {
+                    write!(f, " evicts {:?}", step.left.evicted)?;
+                }
+                write!(f, " holds {:?}", step.left.holds)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use example_sieve_cache::{ClockCache, SieveCache};
+
+    use super::{compare, Side, Stream};
+
+    #[test]
+    fn same_seed_same_stream() {
+        assert_eq!(Stream::from_seed(7), Stream::from_seed(7));
+        assert_ne!(Stream::from_seed(7), Stream::from_seed(8));
+    }
+
+    #[test]
+    fn sieve_and_clock_diverge() {
+        let divergence = (0..100)
+            .find_map(|seed| {
+                compare(
+                    Side {
+                        name: "sieve",
+                        new: SieveCache::with_capacity,
+                    },
+                    Side {
+                        name: "clock",
+                        new: ClockCache::clock,
+                    },
+                    &Stream::from_seed(seed),
+                )
+                .err()
+            })
+            .expect("CLOCK moves survivors, so it should evict differently than SIEVE");
+
+        let last = divergence.steps.last().unwrap();
+        assert_ne!(last.left, last.right);
+        let report = divergence.to_string();
+        assert!(
+            report.starts_with(&format!(
+                "sieve and clock diverge at step {} of seed {}",
+                divergence.steps.len() - 1,
+                divergence.seed
+            )),
+            "{report}"
+        );
+        assert!(
+            report.lines().any(|line| line.starts_with("  > ")),
+            "{report}"
+        );
+    }
+}
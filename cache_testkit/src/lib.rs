@@ -0,0 +1,583 @@
+//! A conformance suite for any [SizeLimitedCache] or [ShareableCache].
+//!
+//! Each check is a plain function that takes a constructor from capacity to cache, so you can call
+//! them from your own tests. The [size_limited_cache_tests] and [shareable_cache_tests] macros
+//! write a `#[test]` for every check:
+//! ```
+//! mod test {
+//!     use cache::synchronized_cache;
+//!     use example_sieve_cache::SieveCache;
+//!
+//!     cache_testkit::size_limited_cache_tests!(SieveCache::with_capacity);
+//!     cache_testkit::shareable_cache_tests!(|capacity| synchronized_cache(
+//!         SieveCache::with_capacity(capacity)
+//!     ));
+//! }
+//! # fn main() {}
+//! ```
+//!
+//! The cache traits only have `get` and `set`, so the checks learn what a cache holds by getting
+//! every key they've set. Caches that implement [RemovableCache] can also run the checks of
+//! `remove` and `clear`, with the [removable_cache_tests] macro.
+//!
+//! SIEVE caches can also be checked step by step against a reference model of SIEVE, with the
+//! [sieve_model_tests] macro. See [sieve_model]. Two caches that should behave alike can be run
+//! side by side on the same seeded streams of gets and sets, with [differential].
+//!
+//! Built with `RUSTFLAGS="--cfg loom"`, `linearizability` checks shareable caches under every
+//! interleaving of a few threads with loom.
+
+use std::collections::HashMap;
+
+pub use cache::{RemovableCache, ResidentKeys, ShareableCache, SizeLimitedCache};
+#[doc(hidden)]
+pub use proptest;
+
+pub mod differential;
+#[cfg(loom)]
+pub mod linearizability;
+pub mod sieve_model;
+
+/// How strictly a cache keeps to its capacity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capacity {
+    /// The cache holds every key until it's full, and then exactly `capacity` keys: one eviction
+    /// per new key.
+    Exact,
+    /// The cache never holds more than `capacity` keys, but may hold fewer. Sharded caches split
+    /// their capacity between shards, and some caches treat their capacity as a target.
+    AtMost,
+}
+
+/// What a cache promises, beyond never holding more than its capacity and never returning a
+/// value other than the last one set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Expectations {
+    pub capacity: Capacity,
+    /// Whether a cache of capacity 0 works. Some caches need room for at least one entry.
+    pub zero_capacity: bool,
+    /// Whether every key set is there right afterwards. Caches with an admission policy, like
+    /// TinyLFU, can turn a new key away instead of evicting an old one for it.
+    pub admits_every_key: bool,
+}
+
+impl Expectations {
+    /// An exact capacity, capacity 0 works, and every key set is admitted.
+    pub const STRICT: Expectations = Expectations {
+        capacity: Capacity::Exact,
+        zero_capacity: true,
+        admits_every_key: true,
+    };
+}
+
+impl Default for Expectations {
+    fn default() -> Self {
+        Self::STRICT
+    }
+}
+
+/// Runs a sequence of gets and sets against a cache and a model of what it may hold: the latest
+/// value set for each key.
+struct Checked<Cache> {
+    cache: Cache,
+    capacity: usize,
+    expectations: Expectations,
+    latest: HashMap<u64, u64>,
+}
+
+impl<Cache> Checked<Cache>
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    fn new(new: impl Fn(usize) -> Cache, capacity: usize, expectations: Expectations) -> Self {
+        Self {
+            cache: new(capacity),
+            capacity,
+            expectations,
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Sets the key without reading it back, which would mark it as used in many caches.
+    fn set_unread(&mut self, key: u64, value: u64) {
+        self.cache.set(key, value);
+        self.latest.insert(key, value);
+    }
+
+    /// Sets the key, and checks that it's visible right away if it should be.
+    fn set(&mut self, key: u64, value: u64) {
+        self.set_unread(key, value);
+        if 0 < self.capacity && self.expectations.admits_every_key {
+            assert_eq!(
+                self.cache.get(&key),
+                Some(value),
+                "key {key} should be visible right after it's set"
+            );
+        }
+    }
+
+    /// Gets the key, and checks that a hit returns the latest value set.
+    fn get(&mut self, key: u64) -> Option<u64> {
+        let value = self.cache.get(&key);
+        if let Some(value) = value {
+            assert_eq!(
+                self.latest.get(&key),
+                Some(&value),
+                "key {key} should have the value it was last set to"
+            );
+        }
+        value
+    }
+
+    /// Gets every key ever set, checks there are no more than the capacity, and returns how
+    /// many were there.
+    fn held(&mut self) -> usize {
+        let mut keys: Vec<u64> = self.latest.keys().copied().collect();
+        keys.sort_unstable();
+        let held = keys
+            .into_iter()
+            .filter(|&key| self.get(key).is_some())
+            .count();
+        assert!(
+            held <= self.capacity,
+            "the cache holds {held} keys, more than its capacity of {}",
+            self.capacity
+        );
+        held
+    }
+}
+
+impl<Cache> Checked<Cache>
+where
+    Cache: RemovableCache<u64, u64>,
+{
+    /// Removes the key, and checks that it returned the latest value set and that it's gone.
+    fn remove(&mut self, key: u64) -> Option<u64> {
+        let removed = self.cache.remove(&key);
+        if let Some(value) = removed {
+            assert_eq!(
+                self.latest.get(&key),
+                Some(&value),
+                "removing key {key} should return the value it was last set to"
+            );
+        }
+        assert_eq!(
+            self.cache.get(&key),
+            None,
+            "key {key} should be gone after it's removed"
+        );
+        removed
+    }
+}
+
+/// A new cache has nothing in it.
+pub fn starts_empty<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    let mut cache = Checked::new(new, 10, expectations);
+    for key in 0..100 {
+        assert_eq!(cache.get(key), None, "a new cache shouldn't have key {key}");
+    }
+}
+
+/// Every key is visible right after it's set, full cache or not, unless the cache doesn't admit
+/// every key.
+pub fn set_keys_are_visible<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    let mut cache = Checked::new(new, 10, expectations);
+    for key in 0..100 {
+        cache.set(key, key * 10);
+    }
+}
+
+/// Setting a key that's already there replaces its value, and doesn't take up more room.
+pub fn overwrite_replaces_value<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    let mut cache = Checked::new(&new, 10, expectations);
+    cache.set(1, 1);
+    cache.set(1, 2);
+    // Getting it checks that it isn't still 1.
+    cache.get(1);
+
+    // Fill the cache, then overwrite every key in the opposite order without reading any of
+    // them. A cache that treats an overwrite as a new key evicts the oldest keys to make room.
+    let mut cache = Checked::new(new, 10, expectations);
+    for key in 0..10 {
+        cache.set_unread(key, key);
+    }
+    for key in (0..10).rev() {
+        cache.set_unread(key, key + 100);
+    }
+    let held = cache.held();
+    if expectations.capacity == Capacity::Exact {
+        assert_eq!(held, 10, "overwriting keys shouldn't evict any of them");
+    }
+
+    for round in 0..5 {
+        for key in 0..10 {
+            cache.set(key, key * 10 + round);
+        }
+    }
+    let held = cache.held();
+    if expectations.capacity == Capacity::Exact {
+        assert_eq!(held, 10, "overwriting keys shouldn't evict any of them");
+    }
+}
+
+/// The cache holds every key until it's full, and evicts one key for each new key after that.
+pub fn evicts_one_per_new_key<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    let mut cache = Checked::new(new, 50, expectations);
+    for key in 0..50 {
+        cache.set(key, key);
+    }
+    let held = cache.held();
+    if expectations.capacity == Capacity::Exact {
+        assert_eq!(held, 50, "a cache filled to capacity should hold every key");
+    }
+    for key in 50..80 {
+        cache.set(key, key);
+        let held = cache.held();
+        if expectations.capacity == Capacity::Exact {
+            assert_eq!(held, 50, "each new key should evict exactly one other");
+        }
+    }
+}
+
+/// A mix of gets, sets and overwrites never leaves more than the capacity in the cache, at any
+/// capacity, including the smallest.
+pub fn never_exceeds_capacity<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    for capacity in [1, 2, 7, 64] {
+        let mut cache = Checked::new(&new, capacity, expectations);
+        let key_space = 4 * capacity as u64;
+        // A fixed pseudorandom sequence, so failures are reproducible.
+        let mut state = 37_u64;
+        for step in 0..20 * key_space {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            let key = (state >> 33) % key_space;
+            if state >> 63 == 0 {
+                cache.set(key, step);
+            } else {
+                cache.get(key);
+            }
+            if step % capacity as u64 == 0 {
+                cache.held();
+            }
+        }
+        cache.held();
+    }
+}
+
+/// A cache with no room holds nothing. Skipped for caches that don't support capacity 0.
+pub fn zero_capacity_holds_nothing<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    if !expectations.zero_capacity {
+        return;
+    }
+    let mut cache = Checked::new(new, 0, expectations);
+    for key in 0..10 {
+        cache.set(key, key);
+    }
+    assert_eq!(cache.held(), 0);
+}
+
+/// Removing a key returns its value and takes only that key out. Removing it again, or removing a
+/// key that was never set, returns nothing.
+pub fn remove_forgets_the_key<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: RemovableCache<u64, u64>,
+{
+    let mut cache = Checked::new(new, 10, expectations);
+    for key in 0..10 {
+        cache.set(key, key * 10);
+    }
+    let held = cache.held();
+    let removed = cache.remove(3);
+    if expectations.admits_every_key && expectations.capacity == Capacity::Exact {
+        assert_eq!(removed, Some(30), "key 3 should have been there to remove");
+    }
+    assert_eq!(
+        cache.held(),
+        held - usize::from(removed.is_some()),
+        "removing a key shouldn't take any other key with it"
+    );
+    assert_eq!(cache.remove(3), None, "key 3 was already removed");
+    assert_eq!(cache.remove(100), None, "key 100 was never set");
+
+    cache.set(3, 31);
+    cache.get(3);
+}
+
+/// Removing keys from a full cache makes room, so as many new keys fit without evicting the
+/// keys that are left.
+pub fn removed_keys_free_their_room<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: RemovableCache<u64, u64>,
+{
+    if expectations.capacity != Capacity::Exact || !expectations.admits_every_key {
+        return;
+    }
+    let mut cache = Checked::new(new, 10, expectations);
+    for key in 0..10 {
+        cache.set_unread(key, key);
+    }
+    for key in 0..5 {
+        cache.remove(key);
+    }
+    for key in 10..15 {
+        cache.set_unread(key, key);
+    }
+    for key in 5..15 {
+        assert_eq!(
+            cache.get(key),
+            Some(key),
+            "key {key} shouldn't be evicted while the removed keys' room is free"
+        );
+    }
+}
+
+/// Clearing a cache removes every key, and the cache fills up again afterwards like a new one.
+pub fn clear_empties_the_cache<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: RemovableCache<u64, u64>,
+{
+    let mut cache = Checked::new(new, 10, expectations);
+    for key in 0..20 {
+        cache.set(key, key);
+    }
+    cache.cache.clear();
+    assert_eq!(cache.held(), 0, "a cleared cache should hold nothing");
+
+    for key in 20..30 {
+        cache.set(key, key);
+    }
+    let held = cache.held();
+    if expectations.capacity == Capacity::Exact && expectations.admits_every_key {
+        assert_eq!(
+            held, 10,
+            "a cleared cache should have room for its capacity"
+        );
+    }
+}
+
+/// Lets the single-threaded checks run on a [ShareableCache].
+pub struct Shared<Cache>(pub Cache);
+
+impl<Cache, Key, Value> SizeLimitedCache<Key, Value> for Shared<Cache>
+where
+    Key: Eq + std::hash::Hash,
+    Value: Clone,
+    Cache: ShareableCache<Key, Value>,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        self.0.get(key)
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        self.0.set(key, value);
+    }
+}
+
+/// Threads setting their own keys at once never see another key's value, and once they're done,
+/// the cache holds no more than its capacity. With an exact capacity that has room for every key,
+/// it holds all of them.
+pub fn concurrent_sets<Cache>(new: impl Fn(usize) -> Cache, expectations: Expectations)
+where
+    Cache: ShareableCache<u64, u64>,
+{
+    const THREADS: u64 = 4;
+    const KEYS_PER_THREAD: u64 = 200;
+    for cache_capacity in [(THREADS * KEYS_PER_THREAD) as usize, 50] {
+        let cache = new(cache_capacity);
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for key in thread * KEYS_PER_THREAD..(thread + 1) * KEYS_PER_THREAD {
+                        cache.set(key, key);
+                        // Another thread can evict it, but it can't change its value.
+                        if let Some(value) = cache.get(&key) {
+                            assert_eq!(value, key);
+                        }
+                    }
+                });
+            }
+        });
+        let held = (0..THREADS * KEYS_PER_THREAD)
+            .filter(|key| cache.get(key).is_some())
+            .count();
+        assert!(
+            held <= cache_capacity,
+            "the cache holds {held} keys, more than its capacity of {cache_capacity}"
+        );
+        if expectations.capacity == Capacity::Exact
+            && cache_capacity as u64 == THREADS * KEYS_PER_THREAD
+        {
+            assert_eq!(
+                held, cache_capacity,
+                "every key fits, so every key should be there"
+            );
+        }
+    }
+}
+
+/// Threads overwriting the same keys at once never leave a value that nobody set.
+pub fn concurrent_overwrites<Cache>(new: impl Fn(usize) -> Cache, _expectations: Expectations)
+where
+    Cache: ShareableCache<u64, u64>,
+{
+    const THREADS: u64 = 4;
+    const KEYS: u64 = 20;
+    let cache = new(KEYS as usize);
+    std::thread::scope(|scope| {
+        for thread in 0..THREADS {
+            let cache = &cache;
+            scope.spawn(move || {
+                for round in 0..500 {
+                    let key = round % KEYS;
+                    cache.set(key, key * 1_000 + thread);
+                    if let Some(value) = cache.get(&key) {
+                        assert_eq!(value / 1_000, key, "key {key} has another key's value");
+                    }
+                }
+            });
+        }
+    });
+    for key in 0..KEYS {
+        if let Some(value) = cache.get(&key) {
+            assert_eq!(value / 1_000, key, "key {key} has another key's value");
+            assert!(value % 1_000 < THREADS, "key {key} has a value nobody set");
+        }
+    }
+}
+
+/// Writes a `#[test]` for each single-threaded check, in a `size_limited_conformance` module.
+///
+/// Pass a constructor from capacity to cache, and optionally the [Expectations] it meets (the
+/// default is [Expectations::STRICT]). Attributes after the expectations, like `#[ignore]`, go on
+/// every test.
+#[macro_export]
+macro_rules! size_limited_cache_tests {
+    ($new:expr) => {
+        $crate::size_limited_cache_tests!($new, $crate::Expectations::STRICT);
+    };
+    ($new:expr, $expectations:expr $(, #[$attribute:meta])* $(,)?) => {
+        mod size_limited_conformance {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::__conformance_tests!(
+                $new,
+                $expectations,
+                [
+                    starts_empty,
+                    set_keys_are_visible,
+                    overwrite_replaces_value,
+                    evicts_one_per_new_key,
+                    never_exceeds_capacity,
+                    zero_capacity_holds_nothing,
+                ]
+                $(, #[$attribute])*
+            );
+        }
+    };
+}
+
+/// Writes a `#[test]` for each check of `remove` and `clear`, in a `removable_conformance`
+/// module, for a [RemovableCache]. It takes the same arguments as [size_limited_cache_tests].
+#[macro_export]
+macro_rules! removable_cache_tests {
+    ($new:expr) => {
+        $crate::removable_cache_tests!($new, $crate::Expectations::STRICT);
+    };
+    ($new:expr, $expectations:expr $(, #[$attribute:meta])* $(,)?) => {
+        mod removable_conformance {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::__conformance_tests!(
+                $new,
+                $expectations,
+                [
+                    remove_forgets_the_key,
+                    removed_keys_free_their_room,
+                    clear_empties_the_cache,
+                ]
+                $(, #[$attribute])*
+            );
+        }
+    };
+}
+
+/// Writes a `#[test]` for every check, run through the [ShareableCache] trait, in a
+/// `shareable_conformance` module. It takes the same arguments as [size_limited_cache_tests].
+#[macro_export]
+macro_rules! shareable_cache_tests {
+    ($new:expr) => {
+        $crate::shareable_cache_tests!($new, $crate::Expectations::STRICT);
+    };
+    ($new:expr, $expectations:expr $(, #[$attribute:meta])* $(,)?) => {
+        mod shareable_conformance {
+            #[allow(unused_imports)]
+            use super::*;
+
+            fn shared(capacity: usize) -> $crate::Shared<impl $crate::ShareableCache<u64, u64>> {
+                $crate::Shared(($new)(capacity))
+            }
+
+            $crate::__conformance_tests!(
+                shared,
+                $expectations,
+                [
+                    starts_empty,
+                    set_keys_are_visible,
+                    overwrite_replaces_value,
+                    evicts_one_per_new_key,
+                    never_exceeds_capacity,
+                    zero_capacity_holds_nothing,
+                ]
+                $(, #[$attribute])*
+            );
+            $crate::__conformance_tests!(
+                $new,
+                $expectations,
+                [concurrent_sets, concurrent_overwrites]
+                $(, #[$attribute])*
+            );
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __conformance_tests {
+    ($new:expr, $expectations:expr, [] $(, #[$attribute:meta])*) => {};
+    (
+        $new:expr,
+        $expectations:expr,
+        [$check:ident $(, $rest:ident)* $(,)?]
+        $(, #[$attribute:meta])*
+    ) => {
+        #[test]
+        $(#[$attribute])*
+        fn $check() {
+            $crate::$check($new, $expectations);
+        }
+
+        $crate::__conformance_tests!($new, $expectations, [$($rest),*] $(, #[$attribute])*);
+    };
+}
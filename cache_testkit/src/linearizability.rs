@@ -0,0 +1,149 @@
+//! Loom model checks for [ShareableCache]s. Only built with `RUSTFLAGS="--cfg loom"`.
+//!
+//! [check_linearizable] runs a few threads' gets and sets against a shared cache under
+//! [loom::model], which runs them again for every order the threads can take their locks and see
+//! each other's atomic writes in. Every run has to be linearizable: there has to be some order of
+//! all the threads' operations that, done one at a time on a single-threaded reference cache, gets
+//! the same results and leaves the same keys behind.
+
+use std::collections::BTreeSet;
+
+use loom::{sync::Arc, thread};
+
+use crate::{sieve_model::Operation, ShareableCache, SizeLimitedCache};
+
+/// What the main thread does to the cache first, and then what each thread does at once.
+///
+/// Loom explores every interleaving, so keep these small: two or three threads of two or three
+/// operations each.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub capacity: usize,
+    pub setup: Vec<Operation>,
+    pub threads: Vec<Vec<Operation>>,
+}
+
+/// Checks that every run of the scenario on `new`'s cache matches some order of its operations
+/// run one at a time on `reference`'s, in what each get returned and in what every key holds at
+/// the end.
+pub fn check_linearizable<Cache, Reference>(
+    new: fn(usize) -> Cache,
+    reference: fn(usize) -> Reference,
+    scenario: Scenario,
+) where
+    Cache: ShareableCache<u64, u64> + 'static,
+    Reference: SizeLimitedCache<u64, u64> + 'static,
+{
+    let keys: BTreeSet<u64> = scenario
+        .setup
+        .iter()
+        .chain(scenario.threads.iter().flatten())
+        .map(|&operation| match operation {
+            Operation::Get(key) | Operation::Set(key, _) => key,
+        })
+        .collect();
+    let orders = interleavings(&scenario.threads);
+
+    loom::model(move || {
+        let cache = Arc::new(new(scenario.capacity));
+        for &operation in &scenario.setup {
+            apply(&*cache, operation);
+        }
+        let handles: Vec<_> = scenario
+            .threads
+            .iter()
+            .map(|operations| {
+                let cache = cache.clone();
+                let operations = operations.clone();
+                thread::spawn(move || {
+                    operations
+                        .into_iter()
+                        .map(|operation| apply(&*cache, operation))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let results: Vec<Vec<Option<u64>>> = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("a cache thread panicked"))
+            .collect();
+        let held: Vec<Option<u64>> = keys.iter().map(|key| cache.get(key)).collect();
+
+        let linearizable = orders.iter().any(|order| {
+            let (expected_results, expected_held) =
+                run_in_order(reference, &scenario, order, &keys);
+            expected_results == results && expected_held == held
+        });
+        assert!(
+            linearizable,
+            "no order of {scenario:?} gets {results:?} and leaves keys {keys:?} holding {held:?}"
+        );
+    });
+}
+
+/// Runs an operation through the shared cache, and returns what a get got.
+fn apply(cache: &impl ShareableCache<u64, u64>, operation: Operation) -> Option<u64> {
+    match operation {
+        Operation::Get(key) => cache.get(&key),
+        Operation::Set(key, value) => {
+            cache.set(key, value);
+            None
+        }
+    }
+}
+
+/// Runs the scenario on a reference cache, taking the threads' next operations in `order`.
+fn run_in_order<Reference>(
+    reference: fn(usize) -> Reference,
+    scenario: &Scenario,
+    order: &[usize],
+    keys: &BTreeSet<u64>,
+) -> (Vec<Vec<Option<u64>>>, Vec<Option<u64>>)
+where
+    Reference: SizeLimitedCache<u64, u64>,
+{
+    let mut cache = reference(scenario.capacity);
+    let mut apply = |operation| match operation {
+        Operation::Get(key) => cache.get(&key),
+        Operation::Set(key, value) => {
+            cache.set(key, value);
+            None
+        }
+    };
+    for &operation in &scenario.setup {
+        apply(operation);
+    }
+    let mut next = vec![0; scenario.threads.len()];
+    let mut results = vec![Vec::new(); scenario.threads.len()];
+    for &thread in order {
+        results[thread].push(apply(scenario.threads[thread][next[thread]]));
+        next[thread] += 1;
+    }
+    let held = keys.iter().map(|&key| apply(Operation::Get(key))).collect();
+    (results, held)
+}
+
+/// Every order to take the threads' operations in, keeping each thread's own order, as a list of
+/// whose turn it is.
+fn interleavings(threads: &[Vec<Operation>]) -> Vec<Vec<usize>> {
+    fn extend(remaining: &mut [usize], order: &mut Vec<usize>, orders: &mut Vec<Vec<usize>>) {
+        if remaining.iter().all(|&count| count == 0) {
+            orders.push(order.clone());
+            return;
+        }
+        for thread in 0..remaining.len() {
+            if 0 < remaining[thread] {
+                remaining[thread] -= 1;
+                order.push(thread);
+                extend(remaining, order, orders);
+                order.pop();
+                remaining[thread] += 1;
+            }
+        }
+    }
+
+    let mut remaining: Vec<usize> = threads.iter().map(Vec::len).collect();
+    let mut orders = Vec::new();
+    extend(&mut remaining, &mut Vec::new(), &mut orders);
+    orders
+}
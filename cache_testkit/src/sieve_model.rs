@@ -0,0 +1,272 @@
+//! Property tests that run random sequences of gets and sets against a SIEVE cache and a
+//! reference model of SIEVE side by side, and fail on the first step where they disagree.
+//!
+//! The [sieve_model_tests](crate::sieve_model_tests) macro writes the tests. When one fails,
+//! proptest shrinks the sequence to the shortest one it can find that still fails, and prints it.
+
+use std::hash::Hash;
+
+use proptest::{collection::vec, prelude::*, test_runner::TestCaseError};
+
+pub use crate::ResidentKeys;
+use crate::SizeLimitedCache;
+
+/// SIEVE written as plainly as possible, to check faster implementations against.
+///
+/// Entries are kept in a list from oldest to newest. A hit marks an entry visited. To make room,
+/// the hand walks from where it last stopped toward the newest entry, wrapping around to the
+/// oldest, clearing visited marks until it finds an entry that isn't marked. It evicts that
+/// entry, and stays where the entry was.
+#[derive(Debug, Clone)]
+pub struct SieveModel<Key, Value> {
+    entries: Vec<ModelEntry<Key, Value>>,
+    hand: usize,
+    capacity: usize,
+}
+
+#[derive(Debug, Clone)]
+struct ModelEntry<Key, Value> {
+    key: Key,
+    value: Value,
+    visited: bool,
+}
+
+impl<Key, Value> SieveModel<Key, Value>
+where
+    Key: Eq + Clone,
+    Value: Clone,
+{
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            hand: 0,
+            capacity,
+        }
+    }
+
+    /// Returns the key's value, and marks it visited.
+    pub fn get(&mut self, key: &Key) -> Option<Value> {
+        let entry = self.entries.iter_mut().find(|entry| entry.key == *key)?;
+        entry.visited = true;
+        Some(entry.value.clone())
+    }
+
+    /// Sets the key, and returns the key evicted to make room for it, if any.
+    ///
+    /// Overwriting a key replaces its value in place and counts as a hit. A cache with capacity
+    /// 0 doesn't keep anything.
+    pub fn set(&mut self, key: Key, value: Value) -> Option<Key> {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.key == key) {
+            entry.value = value;
+            entry.visited = true;
+            return None;
+        }
+        if self.capacity == 0 {
+            return None;
+        }
+
+        let evicted = if self.entries.len() < self.capacity {
+            None
+        } else {
+            while self.entries[self.hand].visited {
+                self.entries[self.hand].visited = false;
+                self.hand = (self.hand + 1) % self.entries.len();
+            }
+            let evicted = self.entries.remove(self.hand);
+            if self.hand == self.entries.len() {
+                self.hand = 0;
+            }
+            Some(evicted.key)
+        };
+        self.entries.push(ModelEntry {
+            key,
+            value,
+            visited: false,
+        });
+        evicted
+    }
+
+    /// The keys in the model, oldest first.
+    pub fn keys(&self) -> Vec<Key> {
+        self.entries.iter().map(|entry| entry.key.clone()).collect()
+    }
+}
+
+/// Lets the model stand in for a cache, for example as the reference in a loom check.
+impl<Key, Value> SizeLimitedCache<Key, Value> for SieveModel<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        SieveModel::get(self, key)
+    }
+
+    fn set(&mut self, key: Key, value: Value) {
+        SieveModel::set(self, key, value);
+    }
+}
+
+/// One step of a generated sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Get(u64),
+    Set(u64, u64),
+}
+
+/// A capacity from 0 to 7, and up to 200 gets and sets over a key space about twice the
+/// capacity, so that the cache is full most of the time and keys come back after they're evicted.
+pub fn scenarios() -> impl Strategy<Value = (usize, Vec<Operation>)> {
+    (0..8_usize).prop_flat_map(|capacity| {
+        let key_space = 2 * capacity as u64 + 2;
+        let operation = prop_oneof![
+            (0..key_space).prop_map(Operation::Get),
+            (0..key_space, 0..4_u64).prop_map(|(key, value)| Operation::Set(key, value)),
+        ];
+        (Just(capacity), vec(operation, 0..200))
+    })
+}
+
+/// Every get returns what the model's does. Eviction decisions only show up when an evicted key
+/// is asked for, so this can report a wrong eviction a few steps after it happened.
+pub fn check_gets<Cache>(
+    new: impl Fn(usize) -> Cache,
+    capacity: usize,
+    operations: &[Operation],
+) -> Result<(), TestCaseError>
+where
+    Cache: SizeLimitedCache<u64, u64>,
+{
+    let mut cache = new(capacity);
+    let mut model = SieveModel::new(capacity);
+    for (step, &operation) in operations.iter().enumerate() {
+        match operation {
+            Operation::Get(key) => {
+                prop_assert_eq!(
+                    cache.get(&key),
+                    model.get(&key),
+                    "step {}: get {}",
+                    step,
+                    key
+                );
+            }
+            Operation::Set(key, value) => {
+                cache.set(key, value);
+                model.set(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every get returns what the model's does, and every set evicts the same key the model's does,
+/// if any.
+pub fn check_evictions<Cache>(
+    new: impl Fn(usize) -> Cache,
+    capacity: usize,
+    operations: &[Operation],
+) -> Result<(), TestCaseError>
+where
+    Cache: SizeLimitedCache<u64, u64> + ResidentKeys<u64>,
+{
+    let mut cache = new(capacity);
+    let mut model = SieveModel::new(capacity);
+    for (step, &operation) in operations.iter().enumerate() {
+        match operation {
+            Operation::Get(key) => {
+                prop_assert_eq!(
+                    cache.get(&key),
+                    model.get(&key),
+                    "step {}: get {}",
+                    step,
+                    key
+                );
+            }
+            Operation::Set(key, value) => {
+                let before = sorted(cache.resident_keys());
+                cache.set(key, value);
+                let after = sorted(cache.resident_keys());
+                let evicted: Vec<u64> = before
+                    .into_iter()
+                    .filter(|key| after.binary_search(key).is_err())
+                    .collect();
+                let expected: Vec<u64> = model.set(key, value).into_iter().collect();
+                prop_assert_eq!(evicted, expected, "step {}: set {}", step, key);
+                prop_assert_eq!(
+                    after,
+                    sorted(model.keys()),
+                    "step {}: resident keys after set {}",
+                    step,
+                    key
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sorted<Key: Ord>(mut keys: Vec<Key>) -> Vec<Key> {
+    keys.sort_unstable();
+    keys
+}
+
+/// Writes proptest tests comparing a SIEVE cache with [SieveModel], in a `sieve_model_tests`
+/// module.
+///
+/// Pass a constructor from capacity to cache. The cache must implement [ResidentKeys] for `u64`
+/// keys. Attributes after the constructor, like `#[ignore]`, go on every test.
+#[macro_export]
+macro_rules! sieve_model_tests {
+    ($new:expr $(, #[$attribute:meta])* $(,)?) => {
+        mod sieve_model_tests {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $crate::proptest::proptest! {
+                #[test]
+                $(#[$attribute])*
+                fn gets_match_the_sieve_model(
+                    (capacity, operations) in $crate::sieve_model::scenarios()
+                ) {
+                    $crate::sieve_model::check_gets($new, capacity, &operations)?;
+                }
+
+                #[test]
+                $(#[$attribute])*
+                fn evictions_match_the_sieve_model(
+                    (capacity, operations) in $crate::sieve_model::scenarios()
+                ) {
+                    $crate::sieve_model::check_evictions($new, capacity, &operations)?;
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::SieveModel;
+
+    #[test]
+    fn model_matches_the_readme_state_flow() {
+        let mut model = SieveModel::new(7);
+        for key in 'a'..='g' {
+            model.set(key, key);
+        }
+        model.get(&'b');
+
+        assert_eq!(model.set('h', 'h'), Some('a'));
+        assert_eq!(model.set('i', 'i'), Some('c'), "the hand passes over b");
+        assert_eq!(model.keys(), vec!['b', 'd', 'e', 'f', 'g', 'h', 'i']);
+    }
+
+    #[test]
+    fn overwrite_is_a_hit() {
+        let mut model = SieveModel::new(2);
+        model.set(1, 1);
+        model.set(2, 2);
+        assert_eq!(model.set(1, 10), None);
+        assert_eq!(model.set(3, 3), Some(2));
+        assert_eq!(model.get(&1), Some(10));
+    }
+}
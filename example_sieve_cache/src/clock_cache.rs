@@ -0,0 +1,363 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+use cache::{RemovableCache, ResidentKeys, SizeLimitedCache, MAX_SIZE};
+
+/// What the hand does with an entry that has been referenced since the hand last saw it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurvivorPolicy {
+    /// CLOCK: the survivor is moved to the head of the list, as though it were newly inserted.
+    /// The hand always examines the oldest entry.
+    MoveToHead,
+    /// SIEVE: the survivor stays where it is, and the hand moves past it toward newer entries.
+    StayInPlace,
+}
+
+/// A generalized SIEVE/CLOCK cache.
+///
+/// Each entry has a small saturating reference counter of `reference_bits` bits instead of the
+/// single `read` bit in the example SieveCache. A read increments the counter, and every time the
+/// hand passes over an entry it decrements the counter. An entry is evicted when the hand finds
+/// its counter at zero.
+///
+/// * 1 bit with `StayInPlace` is SIEVE.
+/// * 1 bit with `MoveToHead` is CLOCK (also known as FIFO-reinsertion or second chance).
+/// * More bits give the counting variants, which let frequently read entries survive several
+///   passes of the hand.
+pub struct ClockCache<Key, Value> {
+    cache: HashMap<Key, ClockEntry<Value>>,
+    /// Oldest entries at the front, newest entries at the back.
+    clock_list: VecDeque<Key>,
+    hand_index: usize,
+    capacity: usize,
+    max_references: u8,
+    survivors: SurvivorPolicy,
+}
+
+struct ClockEntry<Value> {
+    value: Value,
+    references: u8,
+}
+
+impl<Key, Value> SizeLimitedCache<Key, Value> for ClockCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn get(&mut self, key: &Key) -> Option<Value> {
+        let max_references = self.max_references;
+        let value = self.cache.get_mut(key).map(|entry| {
+            entry.references = entry.references.saturating_add(1).min(max_references);
+            entry.value.clone()
+        });
+        self.debug_validate();
+        value
+    }
+
+    /// Overwriting a resident key replaces its value in place and counts as a reference.
+    fn set(&mut self, key: Key, value: Value) {
+        if let Some(entry) = self.cache.get_mut(&key) {
+            entry.value = value;
+            entry.references = entry.references.saturating_add(1).min(self.max_references);
+        } else if 0 < self.capacity {
+            self.make_room_for_one_insertion();
+
+            self.clock_list.push_back(key.clone());
+            self.cache.insert(
+                key,
+                ClockEntry {
+                    value,
+                    references: 0,
+                },
+            );
+        }
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for ClockCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.remove(key).map(|entry| entry.value);
+        if value.is_some() {
+            let index = self
+                .clock_list
+                .iter()
+                .position(|listed| listed == key)
+                .expect("every key in the map is in the clock list");
+            self.clock_list.remove(index);
+            if index < self.hand_index {
+                self.hand_index -= 1;
+            }
+            self.hand_index = self
+                .hand_index
+                .checked_rem(self.clock_list.len())
+                .unwrap_or(0);
+        }
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.cache.clear();
+        self.clock_list.clear();
+        self.hand_index = 0;
+        self.debug_validate();
+    }
+}
+
+/// Lists the keys from oldest to newest, without counting a reference to any of them.
+impl<Key, Value> ResidentKeys<Key> for ClockCache<Key, Value>
+where
+    Key: Clone,
+{
+    fn resident_keys(&self) -> Vec<Key> {
+        self.clock_list.iter().cloned().collect()
+    }
+}
+
+impl<Key, Value> Default for ClockCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::sieve(MAX_SIZE)
+    }
+}
+
+impl<Key, Value> ClockCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    /// Create a new ClockCache holding up to `capacity` items.
+    ///
+    /// `reference_bits` must be between 1 and 8.
+    pub fn new(capacity: usize, reference_bits: u8, survivors: SurvivorPolicy) -> Self {
+        assert!(
+            (1..=8).contains(&reference_bits),
+            "reference_bits must be between 1 and 8, not {reference_bits}"
+        );
+        Self {
+            cache: HashMap::with_capacity(capacity),
+            clock_list: VecDeque::with_capacity(capacity),
+            hand_index: 0,
+            capacity,
+            max_references: u8::MAX >> (8 - reference_bits),
+            survivors,
+        }
+    }
+
+    /// A 1-bit SIEVE cache.
+    pub fn sieve(capacity: usize) -> Self {
+        Self::new(capacity, 1, SurvivorPolicy::StayInPlace)
+    }
+
+    /// A 1-bit CLOCK cache.
+    pub fn clock(capacity: usize) -> Self {
+        Self::new(capacity, 1, SurvivorPolicy::MoveToHead)
+    }
+
+    /// The number of items in the cache.
+    pub fn len(&self) -> usize {
+        self.clock_list.len()
+    }
+
+    /// Whether the cache holds no items.
+    pub fn is_empty(&self) -> bool {
+        self.clock_list.is_empty()
+    }
+
+    /// Checks that the map and the clock list agree, and panics if they don't: they hold the
+    /// same keys, no key is in the list twice, the hand points into the list, no counter is over
+    /// its maximum, and there are no more items than the capacity.
+    pub fn validate(&self) {
+        assert_eq!(
+            self.cache.len(),
+            self.clock_list.len(),
+            "the map and the clock list should hold the same number of keys"
+        );
+        let mut distinct = HashSet::with_capacity(self.clock_list.len());
+        for key in &self.clock_list {
+            assert!(
+                self.cache.contains_key(key),
+                "every key in the clock list should be in the map"
+            );
+            assert!(
+                distinct.insert(key),
+                "no key should be in the clock list twice"
+            );
+        }
+        assert!(
+            self.cache
+                .values()
+                .all(|entry| entry.references <= self.max_references),
+            "no reference counter should be over its maximum"
+        );
+        assert!(
+            self.hand_index < self.clock_list.len().max(1),
+            "the hand should point into the clock list"
+        );
+        assert!(
+            self.clock_list.len() <= self.capacity,
+            "the cache should hold no more than its capacity"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
+    }
+
+    /// Makes sure the cache holds fewer than `capacity` items, so that one more item can be inserted.
+    fn make_room_for_one_insertion(&mut self) {
+        while !self.clock_list.is_empty() && self.capacity <= self.clock_list.len() {
+            let entry = self
+                .cache
+                .get_mut(&self.clock_list[self.hand_index])
+                .expect("every key in the clock list is in the cache");
+
+            if 0 < entry.references {
+                entry.references -= 1;
+                match self.survivors {
+                    SurvivorPolicy::MoveToHead => {
+                        // The hand stays on the oldest entry; the list rotates under it.
+                        if let Some(key) = self.clock_list.remove(self.hand_index) {
+                            self.clock_list.push_back(key);
+                        }
+                    }
+                    SurvivorPolicy::StayInPlace => {
+                        self.hand_index = (self.hand_index + 1) % self.clock_list.len();
+                    }
+                }
+            } else {
+                if let Some(key) = self.clock_list.remove(self.hand_index) {
+                    self.cache.remove(&key);
+                }
+                if self.clock_list.len() <= self.hand_index {
+                    self.hand_index = 0;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use cache::SizeLimitedCache;
+
+    use crate::{ClockCache, SurvivorPolicy};
+
+    mod sieve {
+        use super::*;
+
+        cache_testkit::size_limited_cache_tests!(ClockCache::sieve);
+        cache_testkit::sieve_model_tests!(ClockCache::sieve);
+        cache_testkit::removable_cache_tests!(ClockCache::sieve);
+    }
+
+    mod clock_2bit {
+        use super::*;
+
+        cache_testkit::size_limited_cache_tests!(|capacity| ClockCache::new(
+            capacity,
+            2,
+            SurvivorPolicy::MoveToHead
+        ));
+        cache_testkit::removable_cache_tests!(|capacity| ClockCache::new(
+            capacity,
+            2,
+            SurvivorPolicy::MoveToHead
+        ));
+    }
+
+    fn resident(cache: &ClockCache<char, char>) -> Vec<char> {
+        cache.clock_list.iter().copied().collect()
+    }
+
+    #[test]
+    fn sieve_matches_the_readme_state_flow() {
+        let mut cache = ClockCache::sieve(7);
+        for key in 'a'..='g' {
+            cache.set(key, key);
+        }
+        cache.get(&'b');
+
+        cache.set('h', 'h');
+        assert_eq!(resident(&cache), vec!['b', 'c', 'd', 'e', 'f', 'g', 'h']);
+
+        cache.set('i', 'i');
+        assert_eq!(resident(&cache), vec!['b', 'd', 'e', 'f', 'g', 'h', 'i']);
+        assert_eq!(cache.hand_index, 1, "the sieve hand stays past b");
+    }
+
+    #[test]
+    fn clock_moves_survivors_to_the_head() {
+        let mut cache = ClockCache::clock(3);
+        for key in 'a'..='c' {
+            cache.set(key, key);
+        }
+        cache.get(&'a');
+
+        cache.set('d', 'd');
+        assert_eq!(resident(&cache), vec!['c', 'a', 'd']);
+        assert_eq!(
+            cache.hand_index, 0,
+            "the clock hand always looks at the oldest entry"
+        );
+    }
+
+    #[test]
+    fn counting_variant_survives_several_passes() {
+        let mut cache = ClockCache::new(2, 2, SurvivorPolicy::StayInPlace);
+        cache.set('a', 'a');
+        cache.set('b', 'b');
+        for _ in 0..5 {
+            cache.get(&'a');
+        }
+
+        // 'a' saturates at 3 references, so it outlives 3 new insertions instead of 1.
+        for key in ['c', 'd', 'e'] {
+            cache.set(key, key);
+            assert_eq!(resident(&cache), vec!['a', key]);
+        }
+        cache.set('f', 'f');
+        assert_eq!(resident(&cache), vec!['e', 'f']);
+    }
+
+    #[test]
+    fn eight_bit_counters_saturate_at_255() {
+        let mut cache = ClockCache::new(2, 8, SurvivorPolicy::StayInPlace);
+        cache.set('a', 'a');
+        cache.set('b', 'b');
+        for _ in 0..300 {
+            cache.get(&'a');
+        }
+        assert_eq!(cache.cache[&'a'].references, 255);
+
+        // 'a' outlives 255 new insertions, each costing it one reference, and then goes.
+        let mut new_keys = ('\u{100}'..).take(256);
+        for key in new_keys.by_ref().take(255) {
+            cache.set(key, key);
+            assert_eq!(resident(&cache)[0], 'a');
+        }
+        let last = new_keys.next().unwrap();
+        cache.set(last, last);
+        assert!(!resident(&cache).contains(&'a'));
+    }
+
+    #[test]
+    fn zero_capacity_holds_nothing() {
+        let mut cache = ClockCache::sieve(0);
+        cache.set('a', 'a');
+        assert_eq!(cache.get(&'a'), None);
+        assert!(cache.is_empty());
+    }
+}
@@ -1,4 +1,13 @@
+//! The example SIEVE cache, and the CLOCK family it generalizes to.
+//!
+//! # Checking invariants
+//! [SieveCache::validate] and [ClockCache::validate] panic if a cache's map and list disagree.
+//! The `validate` feature has debug builds check after every operation, and this crate's own
+//! tests check whether it's on or not.
+
+mod clock_cache;
 pub(crate) mod nodes;
 mod sieve_cache;
 
+pub use clock_cache::{ClockCache, SurvivorPolicy};
 pub use sieve_cache::SieveCache;
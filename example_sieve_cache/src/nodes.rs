@@ -1,7 +1,9 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
-};
+use std::sync::atomic::Ordering;
+
+#[cfg(loom)]
+use loom::sync::{atomic::AtomicBool, Arc};
+#[cfg(not(loom))]
+use std::sync::{atomic::AtomicBool, Arc};
 
 /// Helper struct for value reference tracker - goes in the cache map
 pub struct ValueNode<Value> {
@@ -29,6 +31,10 @@ pub fn new_reference_pair<Key, Value>(
     )
 }
 
+// The read flag only uses Relaxed ordering. That's enough because both halves of a pair live in
+// one SieveCache, and every access goes through `&self` or `&mut self` on it: a ShareableCache
+// wraps it in a mutex, and the mutex orders the accesses. The loom tests in sieve_cache.rs check
+// that under every interleaving of the shared cache's threads.
 impl<Key> ReferenceNode<Key> {
     pub fn take_read_state(&self) -> bool {
         self.read.swap(false, Ordering::Relaxed)
@@ -37,6 +43,11 @@ impl<Key> ReferenceNode<Key> {
     pub fn key(&self) -> &Key {
         &self.key
     }
+
+    /// Whether this is the other half of `value`'s pair: whether they share a read flag.
+    pub fn is_paired_with<Value>(&self, value: &ValueNode<Value>) -> bool {
+        Arc::ptr_eq(&self.read, &value.read)
+    }
 }
 
 impl<Value> ValueNode<Value> {
@@ -47,4 +58,8 @@ impl<Value> ValueNode<Value> {
     pub fn value(&self) -> &Value {
         &self.value
     }
+
+    pub fn replace_value(&mut self, value: Value) {
+        self.value = value;
+    }
 }
@@ -1,6 +1,9 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
 
-use cache::{SizeLimitedCache, MAX_SIZE};
+use cache::{RemovableCache, ResidentKeys, SizeLimitedCache, MAX_SIZE};
 
 use crate::nodes::{new_reference_pair, ReferenceNode, ValueNode};
 
@@ -11,6 +14,7 @@ pub struct SieveCache<Key, Value> {
     cache: HashMap<Key, ValueNode<Value>>,
     sieve_list: Vec<ReferenceNode<Key>>,
     hand_index: usize,
+    capacity: usize,
 }
 
 // an implementation must be generic to implement something generically. If you're familiar with c++ this is
@@ -25,19 +29,87 @@ where
     Value: Clone,
 {
     fn get(&mut self, key: &Key) -> Option<Value> {
-        match self.cache.get(key) {
+        let value = match self.cache.get(key) {
             Some(node) => {
                 node.set_read();
                 Some(node.value().clone())
             }
             None => None,
-        }
+        };
+        self.debug_validate();
+        value
     }
 
     fn set(&mut self, key: Key, value: Value) {
-        self.make_room_for_one_insertion();
+        // A key that's already here keeps its place in the sieve list. Pushing it again would
+        // leave a stale reference that evicts the new value when the hand reaches it.
+        if let Some(node) = self.cache.get_mut(&key) {
+            node.replace_value(value);
+            node.set_read();
+        } else if 0 < self.capacity {
+            self.make_room_for_one_insertion();
+            self.insert_new_pair(key, value);
+        }
+        self.debug_validate();
+    }
+}
+
+impl<Key, Value> RemovableCache<Key, Value> for SieveCache<Key, Value>
+where
+    Key: Eq + std::hash::Hash + Clone,
+    Value: Clone,
+{
+    fn remove(&mut self, key: &Key) -> Option<Value> {
+        let value = self.cache.remove(key).map(|node| node.value().clone());
+        if value.is_some() {
+            let index = self
+                .sieve_list
+                .iter()
+                .position(|node| node.key() == key)
+                .expect("every key in the map is in the sieve list");
+            self.sieve_list.remove(index);
+            // The hand keeps pointing at the same node, or at the one after the removed node if
+            // it was pointing at that.
+            if index < self.hand_index {
+                self.hand_index -= 1;
+            }
+            self.hand_index = self
+                .hand_index
+                .checked_rem(self.sieve_list.len())
+                .unwrap_or(0);
+        }
+        self.debug_validate();
+        value
+    }
+
+    fn clear(&mut self) {
+        self.cache.clear();
+        self.sieve_list.clear();
+        self.hand_index = 0;
+        self.debug_validate();
+    }
+}
+
+/// Lists the keys in sieve list order, without marking any of them read.
+impl<Key, Value> ResidentKeys<Key> for SieveCache<Key, Value>
+where
+    Key: Clone,
+{
+    fn resident_keys(&self) -> Vec<Key> {
+        self.sieve_list
+            .iter()
+            .map(|node| node.key().clone())
+            .collect()
+    }
+}
 
-        self.insert_new_pair(key, value);
+impl<Key, Value> Default for SieveCache<Key, Value>
+where
+    Key: Eq + Hash + Clone,
+    Value: Clone,
+{
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -52,16 +124,64 @@ where
 {
     /// Create a new example SieveCache.
     pub fn new() -> Self {
+        Self::with_capacity(MAX_SIZE)
+    }
+
+    /// Create a new example SieveCache holding up to `capacity` items instead of cache::MAX_SIZE.
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
             cache: HashMap::new(),
             sieve_list: Vec::new(),
             hand_index: 0,
+            capacity,
+        }
+    }
+
+    /// Checks that the map and the sieve list agree, and panics if they don't: every key in the
+    /// list has a node in the map that shares its read flag, no key is in the list twice, the
+    /// hand points into the list, and there are no more items than the capacity.
+    pub fn validate(&self) {
+        assert_eq!(
+            self.cache.len(),
+            self.sieve_list.len(),
+            "the map and the sieve list should hold the same number of keys"
+        );
+        for reference in &self.sieve_list {
+            let value = self
+                .cache
+                .get(reference.key())
+                .expect("every key in the sieve list should be in the map");
+            assert!(
+                reference.is_paired_with(value),
+                "a key's list node and map node should share a read flag"
+            );
         }
+        let mut distinct = HashSet::with_capacity(self.sieve_list.len());
+        assert!(
+            self.sieve_list
+                .iter()
+                .all(|reference| distinct.insert(reference.key())),
+            "no key should be in the sieve list twice"
+        );
+        assert!(
+            self.hand_index < self.sieve_list.len().max(1),
+            "the hand should point into the sieve list"
+        );
+        assert!(
+            self.sieve_list.len() <= self.capacity,
+            "the cache should hold no more than its capacity"
+        );
+    }
+
+    /// [Self::validate], in builds that check invariants.
+    fn debug_validate(&self) {
+        #[cfg(all(debug_assertions, any(test, feature = "validate")))]
+        self.validate();
     }
 
-    /// Makes sure the cache doesn't exceed cache::MAX_SIZE - 1 items, so that one more item can be inserted.
+    /// Makes sure the cache doesn't exceed capacity - 1 items, so that one more item can be inserted.
     fn make_room_for_one_insertion(&mut self) {
-        while !self.sieve_list.is_empty() && MAX_SIZE <= self.sieve_list.len() {
+        while !self.sieve_list.is_empty() && self.capacity <= self.sieve_list.len() {
             let node = &self.sieve_list[self.hand_index];
 
             let node_has_been_read_since_last_time_the_hand_checked_it = node.take_read_state();
@@ -72,7 +192,11 @@ where
                 // here's a probably-useless item: Remove it
                 self.cache.remove(node.key());
                 self.sieve_list.remove(self.hand_index);
-                self.hand_index %= self.sieve_list.len();
+                // The list is empty if the capacity is 1, and you can't take a remainder of 0.
+                self.hand_index = self
+                    .hand_index
+                    .checked_rem(self.sieve_list.len())
+                    .unwrap_or(0);
             }
         }
     }
@@ -85,11 +209,35 @@ where
     }
 }
 
-#[cfg(test)]
+// Under loom, the read flags are loom atomics, which only work inside a loom model.
+#[cfg(all(test, not(loom)))]
 mod test {
-    use cache::{SizeLimitedCache, MAX_SIZE};
+    use cache::{synchronized_cache, SizeLimitedCache, MAX_SIZE};
+    use cache_testkit::differential::{self, Side};
 
-    use crate::SieveCache;
+    use crate::{ClockCache, SieveCache};
+
+    cache_testkit::size_limited_cache_tests!(SieveCache::with_capacity);
+    cache_testkit::shareable_cache_tests!(|capacity| synchronized_cache(
+        SieveCache::with_capacity(capacity)
+    ));
+    cache_testkit::sieve_model_tests!(SieveCache::with_capacity);
+    cache_testkit::removable_cache_tests!(SieveCache::with_capacity);
+
+    #[test]
+    fn matches_the_clock_cache_as_sieve() {
+        differential::assert_same(
+            Side {
+                name: "SieveCache",
+                new: SieveCache::with_capacity,
+            },
+            Side {
+                name: "ClockCache::sieve",
+                new: ClockCache::sieve,
+            },
+            0..200,
+        );
+    }
 
     #[test]
     fn one() {
@@ -118,3 +266,76 @@ mod test {
         assert_eq!(count, MAX_SIZE, "the cache should have evicted one element");
     }
 }
+
+// Run these with:
+// RUSTFLAGS="--cfg loom" cargo test -p example_sieve_cache --lib --release --target-dir target/loom
+#[cfg(all(test, loom))]
+mod loom_test {
+    use cache::{synchronized_cache, SynchronizedShareableCache};
+    use cache_testkit::{
+        linearizability::{check_linearizable, Scenario},
+        sieve_model::{Operation::*, SieveModel},
+    };
+
+    use crate::SieveCache;
+
+    fn shared(capacity: usize) -> SynchronizedShareableCache<SieveCache<u64, u64>> {
+        synchronized_cache(SieveCache::with_capacity(capacity))
+    }
+
+    #[test]
+    fn sets_race_for_the_last_slot() {
+        check_linearizable(
+            shared,
+            SieveModel::new,
+            Scenario {
+                capacity: 1,
+                setup: vec![],
+                threads: vec![vec![Set(1, 1), Get(1)], vec![Set(2, 2), Get(2)]],
+            },
+        );
+    }
+
+    #[test]
+    fn read_races_the_hand() {
+        // Whether 1 survives depends on whether the hand sees its read flag.
+        check_linearizable(
+            shared,
+            SieveModel::new,
+            Scenario {
+                capacity: 2,
+                setup: vec![Set(1, 1), Set(2, 2)],
+                threads: vec![vec![Get(1)], vec![Set(3, 3), Get(2)]],
+            },
+        );
+    }
+
+    #[test]
+    fn overwrites_race_with_an_eviction() {
+        check_linearizable(
+            shared,
+            SieveModel::new,
+            Scenario {
+                capacity: 2,
+                setup: vec![Set(1, 1)],
+                threads: vec![
+                    vec![Set(1, 10), Get(1)],
+                    vec![Set(1, 20), Set(2, 2), Set(3, 3)],
+                ],
+            },
+        );
+    }
+
+    #[test]
+    fn three_threads_get_set_and_evict() {
+        check_linearizable(
+            shared,
+            SieveModel::new,
+            Scenario {
+                capacity: 2,
+                setup: vec![Set(1, 1), Set(2, 2)],
+                threads: vec![vec![Get(1)], vec![Set(3, 3)], vec![Get(2), Set(4, 4)]],
+            },
+        );
+    }
+}
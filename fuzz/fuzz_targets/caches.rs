@@ -0,0 +1,104 @@
+#![no_main]
+
+//! Drives every policy with arbitrary gets and sets. No cache may panic, a get may only return
+//! the last value set for its key, and no cache may end up holding more keys than its capacity.
+
+use std::collections::HashMap;
+
+use arbitrary::Arbitrary;
+use benchmarking::policy::{CacheVisitor, Policy};
+use cache::SizeLimitedCache;
+use cache_policies::next_use_indices;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    policy: u8,
+    capacity: u8,
+    operations: Vec<Operation>,
+}
+
+#[derive(Debug, Clone, Copy, Arbitrary)]
+enum Operation {
+    Get(u8),
+    Set(u8, u16),
+}
+
+impl Operation {
+    fn key(self) -> u8 {
+        match self {
+            Operation::Get(key) | Operation::Set(key, _) => key,
+        }
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let policy = Policy::ALL[usize::from(input.policy) % Policy::ALL.len()];
+    // Small capacities, so that most operations evict.
+    let mut capacity = usize::from(input.capacity % 17);
+    if capacity == 0 && !supports_zero_capacity(policy) {
+        capacity = 1;
+    }
+    // Belady treats every get and set as the next request of its trace. The future it's given
+    // is only a guess when a set follows a missed get, but it must cope with that too.
+    let next_uses = || next_use_indices(input.operations.iter().map(|&op| op.key())).into();
+
+    policy.build(
+        capacity,
+        next_uses,
+        Check {
+            policy,
+            capacity,
+            operations: &input.operations,
+        },
+    );
+});
+
+/// k-cache's segmented cache and the lru crate both need room for at least one entry.
+fn supports_zero_capacity(policy: Policy) -> bool {
+    !matches!(policy, Policy::KCache | Policy::LruCrate)
+}
+
+struct Check<'a> {
+    policy: Policy,
+    capacity: usize,
+    operations: &'a [Operation],
+}
+
+impl CacheVisitor<u8, u16> for Check<'_> {
+    type Output = ();
+
+    fn visit(self, mut cache: impl SizeLimitedCache<u8, u16> + Send) {
+        let mut latest = HashMap::new();
+        for &operation in self.operations {
+            match operation {
+                Operation::Get(key) => {
+                    if let Some(value) = cache.get(&key) {
+                        assert_eq!(
+                            latest.get(&key),
+                            Some(&value),
+                            "{} returned a value that was never set for key {key}",
+                            self.policy.name()
+                        );
+                    }
+                }
+                Operation::Set(key, value) => {
+                    cache.set(key, value);
+                    latest.insert(key, value);
+                }
+            }
+        }
+
+        // moka and mini-moka apply evictions in batches, so they can be over capacity for a while.
+        if matches!(self.policy, Policy::Lru | Policy::MiniMoka) {
+            return;
+        }
+        let held = latest.keys().filter(|key| cache.get(key).is_some()).count();
+        assert!(
+            held <= self.capacity,
+            "{} holds {held} keys, more than its capacity of {}",
+            self.policy.name(),
+            self.capacity
+        );
+    }
+}
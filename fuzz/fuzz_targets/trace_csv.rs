@@ -0,0 +1,17 @@
+#![no_main]
+
+use benchmarking::trace::{CsvColumns, TraceFormat};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Every optional column, so that every field parser sees arbitrary text.
+    let columns = CsvColumns {
+        delimiter: ',',
+        has_header: true,
+        key: 1,
+        size: Some(2),
+        op: Some(3),
+        timestamp: Some(0),
+    };
+    cache_fuzz::read_trace(data, TraceFormat::Csv, &columns);
+});
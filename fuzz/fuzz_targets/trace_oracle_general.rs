@@ -0,0 +1,8 @@
+#![no_main]
+
+use benchmarking::trace::{CsvColumns, TraceFormat};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    cache_fuzz::read_trace(data, TraceFormat::OracleGeneral, &CsvColumns::default());
+});
@@ -0,0 +1,21 @@
+//! Shared code for the fuzz targets in `fuzz_targets/`.
+
+use benchmarking::trace::{CsvColumns, TraceFormat, TraceReader};
+
+/// A trace can legitimately expand to far more requests than it has bytes: one ARC line stands
+/// for any number of blocks. Reading stops here, so the fuzzer spends its time on new inputs
+/// rather than on long ones.
+pub const MAX_REQUESTS: usize = 100_000;
+
+/// Reads a trace of arbitrary bytes the way the simulator does, up to the first error. Whatever
+/// the bytes are, the readers must return errors rather than panic.
+pub fn read_trace(data: &[u8], format: TraceFormat, columns: &CsvColumns) {
+    let Ok(reader) = TraceReader::new(data, format, columns) else {
+        return;
+    };
+    for request in reader.take(MAX_REQUESTS) {
+        if request.is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::{any::Any, sync::Arc, time::Duration};
+
+use rand::Rng;
+use tokio::time::Instant;
+
+/// A loader error with its type erased, so that loaders with different error types can share a
+/// cache and its loads in flight.
+pub(crate) type ErasedError = Arc<dyn Any + Send + Sync>;
+
+/// What a load found for a key.
+#[derive(Debug, Clone)]
+pub enum Loaded<Value> {
+    Value(Value),
+    /// The loader found nothing for the key.
+    Absent,
+    /// The loader failed, with an error of whichever type its loader had.
+    Error(Arc<dyn Any + Send + Sync>),
+}
+
+/// What a load found, when it was loaded or set, and how long it took to load.
+///
+/// This is what a [LoadingCache](crate::LoadingCache) keeps in the cache it wraps, so that it can
+/// tell how old each value is. Entries are values unless the cache keeps negative results, with
+/// [with_negative_expire_after](crate::LoadingCache::with_negative_expire_after).
+#[derive(Debug, Clone)]
+pub struct Entry<Value> {
+    loaded: Loaded<Value>,
+    loaded_at: Instant,
+    load_time: Duration,
+}
+
+impl<Value> Entry<Value> {
+    /// A load that took `load_time`, and finished just now.
+    pub(crate) fn new(loaded: Loaded<Value>, load_time: Duration) -> Self {
+        Self {
+            loaded,
+            loaded_at: Instant::now(),
+            load_time,
+        }
+    }
+
+    /// A value set just now. It took no time to load.
+    pub(crate) fn set(value: Value) -> Self {
+        Self::new(Loaded::Value(value), Duration::ZERO)
+    }
+
+    pub fn loaded(&self) -> &Loaded<Value> {
+        &self.loaded
+    }
+
+    pub fn into_loaded(self) -> Loaded<Value> {
+        self.loaded
+    }
+
+    /// The value, or None if the load found nothing or failed.
+    pub fn value(&self) -> Option<&Value> {
+        match &self.loaded {
+            Loaded::Value(value) => Some(value),
+            Loaded::Absent | Loaded::Error(_) => None,
+        }
+    }
+
+    pub fn loaded_at(&self) -> Instant {
+        self.loaded_at
+    }
+
+    pub fn load_time(&self) -> Duration {
+        self.load_time
+    }
+}
+
+/// How long a [LoadingCache](crate::LoadingCache)'s values last. By default they last until
+/// they're evicted.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Expiry {
+    /// How old a value can get before a read reloads it in the background.
+    pub refresh_after: Option<Duration>,
+    /// How old a value can get before a read waits for a reload instead.
+    pub expire_after: Option<Duration>,
+    /// How long after it expires a value is still served, if reloading it fails.
+    pub stale_for: Duration,
+    /// XFetch's beta: how eagerly reads reload values before they expire.
+    pub early_expiration: Option<f64>,
+    /// How long absent results and errors are kept, if they're kept at all.
+    pub negative_expire_after: Option<Duration>,
+}
+
+/// What a read should do with an entry, by its age.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Freshness {
+    /// Serve it.
+    Fresh,
+    /// Serve it, and reload it in the background.
+    RefreshDue,
+    /// Reload it, and serve it if the reload fails.
+    Stale,
+    /// Reload it. It's too old to serve.
+    Expired,
+}
+
+impl Expiry {
+    /// Absent results and errors are served until `negative_expire_after`, and then reloaded.
+    /// They're never refreshed in the background, or served stale.
+    pub fn freshness<Value>(&self, entry: &Entry<Value>, now: Instant) -> Freshness {
+        let age = now.saturating_duration_since(entry.loaded_at);
+        if !matches!(entry.loaded, Loaded::Value(_)) {
+            return match self.negative_expire_after {
+                Some(negative_expire_after) if age < negative_expire_after => Freshness::Fresh,
+                _ => Freshness::Expired,
+            };
+        }
+        match self.expire_after {
+            Some(expire_after) if expire_after + self.stale_for <= age => Freshness::Expired,
+            Some(expire_after) if expire_after <= age => Freshness::Stale,
+            _ => match self.refresh_after {
+                Some(refresh_after) if refresh_after <= age => Freshness::RefreshDue,
+                _ => Freshness::Fresh,
+            },
+        }
+    }
+
+    /// Whether a read of a value that hasn't expired yet should reload it anyway, by XFetch.
+    ///
+    /// XFetch reloads a value when `now - load_time * beta * ln(random) >= expiry`, for a random
+    /// number from 0 to 1. The chance rises smoothly to 1 as the value nears its expiry, and
+    /// values that are slow to load start earlier. Each reader draws its own chance, so caches
+    /// that loaded a value at the same time reload it at different times.
+    pub fn expires_early<Value>(
+        &self,
+        entry: &Entry<Value>,
+        now: Instant,
+        random: &mut impl Rng,
+    ) -> bool {
+        let (Some(beta), Some(expire_after), Loaded::Value(_)) =
+            (self.early_expiration, self.expire_after, &entry.loaded)
+        else {
+            return false;
+        };
+        let expires_in = (entry.loaded_at + expire_after).saturating_duration_since(now);
+        // 1 - [0, 1) is (0, 1], so the logarithm is finite and not positive.
+        let head_start = entry.load_time.as_secs_f64() * beta * -(1.0 - random.gen::<f64>()).ln();
+        expires_in.as_secs_f64() <= head_start
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use tokio::time::{advance, Instant};
+
+    use super::{Entry, Expiry, Freshness, Loaded};
+
+    fn early_reloads(entry: &Entry<u64>, expiry: &Expiry) -> usize {
+        let mut random = StdRng::seed_from_u64(1);
+        (0..1000)
+            .filter(|_| expiry.expires_early(entry, Instant::now(), &mut random))
+            .count()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn the_chance_of_expiring_early_rises_toward_expiry() {
+        let expiry = Expiry {
+            expire_after: Some(Duration::from_secs(60)),
+            early_expiration: Some(1.0),
+            ..Expiry::default()
+        };
+        let loaded = Entry::new(Loaded::Value(7), Duration::from_secs(1));
+        let set = Entry::set(7);
+
+        assert_eq!(early_reloads(&loaded, &expiry), 0, "a minute out");
+        advance(Duration::from_secs(58)).await;
+        let two_seconds_out = early_reloads(&loaded, &expiry);
+        advance(Duration::from_millis(1500)).await;
+        let half_a_second_out = early_reloads(&loaded, &expiry);
+        assert!(
+            0 < two_seconds_out && two_seconds_out < half_a_second_out,
+            "{two_seconds_out} then {half_a_second_out} of 1000"
+        );
+        assert_eq!(
+            early_reloads(&set, &expiry),
+            0,
+            "set values took no time to load"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn negative_entries_expire_on_their_own_schedule() {
+        let expiry = Expiry {
+            expire_after: Some(Duration::from_secs(60)),
+            stale_for: Duration::from_secs(60),
+            negative_expire_after: Some(Duration::from_secs(5)),
+            ..Expiry::default()
+        };
+        let value = Entry::set(7);
+        let absent = Entry::<u64>::new(Loaded::Absent, Duration::from_secs(1));
+
+        assert_eq!(expiry.freshness(&absent, Instant::now()), Freshness::Fresh);
+        advance(Duration::from_secs(5)).await;
+        assert_eq!(
+            expiry.freshness(&absent, Instant::now()),
+            Freshness::Expired,
+            "absent results aren't served stale"
+        );
+        assert_eq!(expiry.freshness(&value, Instant::now()), Freshness::Fresh);
+    }
+}
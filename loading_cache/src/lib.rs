@@ -0,0 +1,7 @@
+mod expiry;
+mod loading_cache;
+mod stats;
+
+pub use expiry::{Entry, Loaded};
+pub use loading_cache::LoadingCache;
+pub use stats::LoadingStats;
@@ -0,0 +1,822 @@
+use std::{collections::HashMap, future::Future, hash::Hash, sync::Arc, time::Duration};
+
+use cache::{AsyncCache, SizeLimitedCache};
+use rand::{rngs::StdRng, SeedableRng};
+use tokio::{
+    sync::{watch, Mutex},
+    time::Instant,
+};
+
+use crate::{
+    expiry::{Entry, ErasedError, Expiry, Freshness, Loaded},
+    stats::{Counters, LoadingStats},
+};
+
+/// What a load returns: a value, nothing, or an error with its type erased so loads with different
+/// error types can share the map of loads in flight.
+type LoadResult<Value> = Result<Option<Value>, ErasedError>;
+
+/// An [AsyncCache] that loads missing values in Tokio tasks.
+///
+/// It wraps any [SizeLimitedCache] in an async mutex, so a task waiting its turn yields to the
+/// executor instead of blocking one of its threads. The first caller to miss a key spawns a task
+/// to load it, and every caller for that key waits on that one task. The load finishes and caches
+/// its value even if every caller gives up on it, which is why it must be called from within a
+/// Tokio runtime.
+///
+/// Values last until they're evicted, unless you give them an age to be refreshed at, expire at,
+/// or be served stale for, with the `with_` methods. Loads that find nothing or fail aren't
+/// cached, unless you give them an age to expire at too.
+///
+/// Clones share the same cache.
+pub struct LoadingCache<Cache, Key, Value> {
+    state: Arc<Mutex<State<Cache, Key, Value>>>,
+    expiry: Expiry,
+    counters: Arc<Counters>,
+}
+
+struct State<Cache, Key, Value> {
+    cache: Cache,
+    loads: HashMap<Key, Load<Value>>,
+    next_load_id: u64,
+    /// For early expiration.
+    random: StdRng,
+}
+
+/// A load in flight.
+struct Load<Value> {
+    /// Tells this load apart from a later load of the same key.
+    id: u64,
+    /// None until the load finishes. If the loader panics, the sender is dropped without a result.
+    result: watch::Receiver<Option<LoadResult<Value>>>,
+}
+
+impl<Cache, Key, Value> State<Cache, Key, Value>
+where
+    Key: Eq + Hash,
+{
+    /// Where the result of the key's load in flight will be, if it has one.
+    fn load_in_flight(&self, key: &Key) -> Option<watch::Receiver<Option<LoadResult<Value>>>> {
+        // A load whose sender is gone without a result panicked, so it's no use waiting.
+        self.loads
+            .get(key)
+            .filter(|load| load.result.has_changed().is_ok())
+            .map(|load| load.result.clone())
+    }
+}
+
+impl<Cache, Key, Value> LoadingCache<Cache, Key, Value>
+where
+    Cache: SizeLimitedCache<Key, Entry<Value>> + Send + 'static,
+    Key: Eq + Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    /// Wraps a cache, which decides what to keep when it's full.
+    pub fn new(cache: Cache) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                cache,
+                loads: HashMap::new(),
+                next_load_id: 0,
+                random: StdRng::from_entropy(),
+            })),
+            expiry: Expiry::default(),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Reloads a value in the background when a read finds it older than `refresh_after`. That
+    /// read, and the reads after it until the reload finishes, get the old value right away.
+    pub fn with_refresh_after(self, refresh_after: Duration) -> Self {
+        Self {
+            expiry: Expiry {
+                refresh_after: Some(refresh_after),
+                ..self.expiry
+            },
+            ..self
+        }
+    }
+
+    /// Doesn't serve values older than `expire_after`. A read that finds one waits for a reload.
+    pub fn with_expire_after(self, expire_after: Duration) -> Self {
+        Self {
+            expiry: Expiry {
+                expire_after: Some(expire_after),
+                ..self.expiry
+            },
+            ..self
+        }
+    }
+
+    /// When reloading an expired value fails, serves the expired value instead of the error, for
+    /// up to `stale_for` after it expired.
+    pub fn with_stale_for(self, stale_for: Duration) -> Self {
+        Self {
+            expiry: Expiry {
+                stale_for,
+                ..self.expiry
+            },
+            ..self
+        }
+    }
+
+    /// Reloads values in the background a little before they expire, at random, by XFetch.
+    ///
+    /// Each read of a value that's nearing its expiry reloads it early with a chance that rises
+    /// as the expiry gets closer. How soon the chance starts to rise scales with how long the
+    /// value took to load, times `beta`. Caches that loaded a value at the same time, in
+    /// different processes, then reload it at different times instead of all at once when it
+    /// expires. A `beta` of 1 is a good start, and more reloads earlier.
+    ///
+    /// It needs [Self::with_expire_after]. Values that were set rather than loaded took no time
+    /// to load, so they never expire early.
+    pub fn with_early_expiration(self, beta: f64) -> Self {
+        assert!(0.0 < beta, "beta must be positive, not {beta}");
+        Self {
+            expiry: Expiry {
+                early_expiration: Some(beta),
+                ..self.expiry
+            },
+            ..self
+        }
+    }
+
+    /// Caches loads that find nothing, from [Self::get_or_try_insert_optional_with], and loads
+    /// that fail, for `negative_expire_after`, which is usually shorter than values get. Until
+    /// then, reads of the key get nothing or the error again without loading, and count as
+    /// absent or error hits, so a missing key or a failing backend isn't hit on every read.
+    ///
+    /// A failed reload doesn't replace a value that can still be served, fresh or stale.
+    /// Without this, a load that finds nothing or fails leaves the key as it was.
+    pub fn with_negative_expire_after(self, negative_expire_after: Duration) -> Self {
+        Self {
+            expiry: Expiry {
+                negative_expire_after: Some(negative_expire_after),
+                ..self.expiry
+            },
+            ..self
+        }
+    }
+
+    /// Seeds early expiration's chances, so that tests can repeat them.
+    #[cfg(test)]
+    fn with_random_seed(mut self, seed: u64) -> Self {
+        Arc::get_mut(&mut self.state)
+            .expect("seed the cache before cloning it")
+            .get_mut()
+            .random = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// What the cache has done since it was made.
+    pub fn stats(&self) -> LoadingStats {
+        self.counters.stats()
+    }
+
+    /// Like [AsyncCache::get_or_try_insert_with], for loaders that can find nothing for a key.
+    /// Then `init` returns None, and so does this, for every caller waiting on the load.
+    ///
+    /// Absent results are only cached with [Self::with_negative_expire_after]. A cached one reads
+    /// as None here, and as a miss in [AsyncCache::get_or_try_insert_with], which needs a value.
+    ///
+    /// # Panics
+    /// If the loader panics, every caller waiting on it panics too. The next call starts a new
+    /// load.
+    pub async fn get_or_try_insert_optional_with<Init, Error>(
+        &self,
+        key: Key,
+        init: Init,
+    ) -> Result<Option<Value>, Arc<Error>>
+    where
+        Init: Future<Output = Result<Option<Value>, Error>> + Send + 'static,
+        Error: Send + Sync + 'static,
+    {
+        self.load(key, init, true).await
+    }
+
+    /// Spawns a task that runs `init` and caches what it finds, and returns where its result will
+    /// be.
+    fn start_load<Init, Error>(
+        &self,
+        state: &mut State<Cache, Key, Value>,
+        key: Key,
+        init: Init,
+    ) -> watch::Receiver<Option<LoadResult<Value>>>
+    where
+        Init: Future<Output = Result<Option<Value>, Error>> + Send + 'static,
+        Error: Send + Sync + 'static,
+    {
+        let (sender, result) = watch::channel(None);
+        let id = state.next_load_id;
+        state.next_load_id += 1;
+        state.loads.insert(
+            key.clone(),
+            Load {
+                id,
+                result: result.clone(),
+            },
+        );
+
+        let shared_state = self.state.clone();
+        let counters = self.counters.clone();
+        let expiry = self.expiry;
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let result = init.await.map_err(|error| Arc::new(error) as ErasedError);
+            let load_time = started.elapsed();
+            match &result {
+                Ok(Some(_)) => {}
+                Ok(None) => Counters::count(&counters.absent_loads),
+                Err(_) => Counters::count(&counters.load_errors),
+            }
+            let caches_negatives = expiry.negative_expire_after.is_some();
+            let mut state = shared_state.lock().await;
+            // A set while the load was in flight removes it. The set's value is newer, so the
+            // callers waiting get the load's value but the cache keeps the set's.
+            if state.loads.get(&key).is_some_and(|load| load.id == id) {
+                state.loads.remove(&key);
+                let loaded = match &result {
+                    Ok(Some(value)) => Some(Loaded::Value(value.clone())),
+                    Ok(None) => caches_negatives.then_some(Loaded::Absent),
+                    // An error says nothing about the value, so a value that can still be served
+                    // is better than the error.
+                    Err(error) => {
+                        let servable = state.cache.get(&key).is_some_and(|entry| {
+                            entry.value().is_some()
+                                && expiry.freshness(&entry, Instant::now()) != Freshness::Expired
+                        });
+                        (caches_negatives && !servable).then(|| Loaded::Error(error.clone()))
+                    }
+                };
+                if let Some(loaded) = loaded {
+                    state.cache.set(key, Entry::new(loaded, load_time));
+                }
+            }
+            sender.send_replace(Some(result));
+        });
+        result
+    }
+
+    /// Gets what's cached for the key, or loads it with `init`. Both loading methods read through
+    /// here. Unless `accepts_absent`, a cached absent result is a miss, and so is another caller's
+    /// load that found nothing, which this caller then loads again with its own loader.
+    async fn load<Init, Error>(
+        &self,
+        key: Key,
+        init: Init,
+        accepts_absent: bool,
+    ) -> Result<Option<Value>, Arc<Error>>
+    where
+        Init: Future<Output = Result<Option<Value>, Error>> + Send + 'static,
+        Error: Send + Sync + 'static,
+    {
+        let mut init = Some(init);
+        loop {
+            let (mut result, stale) = {
+                let mut state = self.state.lock().await;
+                let mut stale = None;
+                if let Some(entry) = state.cache.get(&key) {
+                    let now = Instant::now();
+                    let freshness = self.expiry.freshness(&entry, now);
+                    let reload = match freshness {
+                        Freshness::RefreshDue => Some(&self.counters.refreshes),
+                        Freshness::Fresh
+                            if self.expiry.expires_early(&entry, now, &mut state.random) =>
+                        {
+                            Some(&self.counters.early_refreshes)
+                        }
+                        _ => None,
+                    };
+                    match (freshness, entry.into_loaded()) {
+                        (Freshness::Fresh | Freshness::RefreshDue, Loaded::Value(value)) => {
+                            Counters::count(&self.counters.hits);
+                            if let Some(counter) = reload {
+                                if state.load_in_flight(&key).is_none() {
+                                    if let Some(init) = init.take() {
+                                        Counters::count(counter);
+                                        self.start_load(&mut state, key, init);
+                                    }
+                                }
+                            }
+                            return Ok(Some(value));
+                        }
+                        (Freshness::Fresh, Loaded::Absent) if accepts_absent => {
+                            Counters::count(&self.counters.absent_hits);
+                            return Ok(None);
+                        }
+                        // An error from a loader with another error type isn't this caller's to
+                        // return, so it's a miss.
+                        (Freshness::Fresh, Loaded::Error(error)) => {
+                            if let Ok(error) = error.downcast::<Error>() {
+                                Counters::count(&self.counters.error_hits);
+                                return Err(error);
+                            }
+                        }
+                        (Freshness::Stale, Loaded::Value(value)) => stale = Some(value),
+                        _ => {}
+                    }
+                }
+                Counters::count(&self.counters.misses);
+                let result = match state.load_in_flight(&key) {
+                    Some(result) => result,
+                    None => {
+                        let init = init
+                            .take()
+                            .expect("only a load someone else started is waited on twice");
+                        self.start_load(&mut state, key.clone(), init)
+                    }
+                };
+                (result, stale)
+            };
+
+            let result = result
+                .wait_for(Option::is_some)
+                .await
+                .expect("the loader panicked")
+                .clone()
+                .expect("the load has finished");
+            match result {
+                Ok(Some(value)) => return Ok(Some(value)),
+                Ok(None) if accepts_absent => return Ok(None),
+                // Another caller's loader found nothing, but this caller needs a value.
+                Ok(None) => continue,
+                Err(error) => match error.downcast::<Error>() {
+                    Ok(error) => match stale {
+                        Some(value) => {
+                            Counters::count(&self.counters.stale_serves);
+                            return Ok(Some(value));
+                        }
+                        None => return Err(error),
+                    },
+                    // Another caller's loader with another error type failed. Its error isn't
+                    // this caller's to return, so try again with this caller's loader.
+                    Err(_) => continue,
+                },
+            }
+        }
+    }
+}
+
+impl<Cache, Key, Value> Clone for LoadingCache<Cache, Key, Value> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            expiry: self.expiry,
+            counters: self.counters.clone(),
+        }
+    }
+}
+
+impl<Cache, Key, Value> AsyncCache<Key, Value> for LoadingCache<Cache, Key, Value>
+where
+    Cache: SizeLimitedCache<Key, Entry<Value>> + Send + 'static,
+    Key: Eq + Hash + Clone + Send + Sync + 'static,
+    Value: Clone + Send + Sync + 'static,
+{
+    /// Gets a value that hasn't expired. There's no loader to refresh it with, so this never
+    /// starts a refresh.
+    /// Absent results and errors that are cached read as None.
+    async fn get(&self, key: &Key) -> Option<Value> {
+        let entry = self.state.lock().await.cache.get(key);
+        let loaded = entry
+            .filter(|entry| {
+                matches!(
+                    self.expiry.freshness(entry, Instant::now()),
+                    Freshness::Fresh | Freshness::RefreshDue
+                )
+            })
+            .map(Entry::into_loaded);
+        match loaded {
+            Some(Loaded::Value(value)) => {
+                Counters::count(&self.counters.hits);
+                Some(value)
+            }
+            Some(Loaded::Absent) => {
+                Counters::count(&self.counters.absent_hits);
+                None
+            }
+            Some(Loaded::Error(_)) => {
+                Counters::count(&self.counters.error_hits);
+                None
+            }
+            None => {
+                Counters::count(&self.counters.misses);
+                None
+            }
+        }
+    }
+
+    async fn set(&self, key: Key, value: Value) {
+        let mut state = self.state.lock().await;
+        state.loads.remove(&key);
+        state.cache.set(key, Entry::set(value));
+    }
+
+    /// A value past its refresh age, or expiring early, is served, and `init` reloads it in the
+    /// background unless it's already reloading. A value past its expiry is reloaded like a
+    /// missing one, but if the reload fails while the value is within its stale period, the caller
+    /// gets the value.
+    ///
+    /// Loader errors are cached like absent results, if the cache was built
+    /// [with_negative_expire_after](LoadingCache::with_negative_expire_after).
+    ///
+    /// # Panics
+    /// If the loader panics, every caller waiting on it panics too. The next call starts a new
+    /// load.
+    async fn get_or_try_insert_with<Init, Error>(
+        &self,
+        key: Key,
+        init: Init,
+    ) -> Result<Value, Arc<Error>>
+    where
+        Init: Future<Output = Result<Value, Error>> + Send + 'static,
+        Error: Send + Sync + 'static,
+    {
+        self.load(key, async move { init.await.map(Some) }, false)
+            .await
+            .map(|value| value.expect("a load that can find nothing isn't accepted"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use cache::AsyncCache;
+    use example_sieve_cache::SieveCache;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use tokio::time::sleep;
+
+    use crate::{Entry, LoadingCache};
+
+    type TestCache = LoadingCache<SieveCache<u64, Entry<u64>>, u64, u64>;
+
+    fn new_cache() -> TestCache {
+        LoadingCache::new(SieveCache::with_capacity(10))
+    }
+
+    /// A loader that counts its calls, and takes a second to return `result`.
+    fn slow_loader<Loaded: Send + 'static>(
+        loads: &Arc<AtomicUsize>,
+        result: Result<Loaded, &'static str>,
+    ) -> impl std::future::Future<Output = Result<Loaded, &'static str>> + Send + 'static {
+        let loads = loads.clone();
+        async move {
+            loads.fetch_add(1, Ordering::SeqCst);
+            sleep(Duration::from_secs(1)).await;
+            result
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_callers_share_one_load() {
+        let cache = new_cache();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let callers: Vec<_> = (0..10)
+            .map(|_| {
+                let cache = cache.clone();
+                let init = slow_loader(&loads, Ok(7));
+                tokio::spawn(async move { cache.get_or_try_insert_with(1, init).await })
+            })
+            .collect();
+        for caller in callers {
+            assert_eq!(caller.await.unwrap(), Ok(7));
+        }
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert_eq!(cache.get(&1).await, Some(7), "the loaded value is cached");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn dropping_a_caller_does_not_cancel_the_load() {
+        let cache = new_cache();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let first = tokio::spawn({
+            let cache = cache.clone();
+            let init = slow_loader(&loads, Ok(7));
+            async move { cache.get_or_try_insert_with(1, init).await }
+        });
+        let second = tokio::spawn({
+            let cache = cache.clone();
+            let init = slow_loader(&loads, Ok(8));
+            async move { cache.get_or_try_insert_with(1, init).await }
+        });
+        sleep(Duration::from_millis(100)).await;
+        first.abort();
+
+        assert_eq!(second.await.unwrap(), Ok(7), "the first caller's load");
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_load_every_caller_gave_up_on_is_still_cached() {
+        let cache = new_cache();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let only = tokio::spawn({
+            let cache = cache.clone();
+            let init = slow_loader(&loads, Ok(7));
+            async move { cache.get_or_try_insert_with(1, init).await }
+        });
+        sleep(Duration::from_millis(100)).await;
+        only.abort();
+
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(cache.get(&1).await, Some(7));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn errors_go_to_every_caller_and_are_not_cached() {
+        let cache = new_cache();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let (first, second) = tokio::join!(
+            cache.get_or_try_insert_with(1, slow_loader(&loads, Err("down"))),
+            cache.get_or_try_insert_with(1, slow_loader(&loads, Err("down"))),
+        );
+        let (first, second) = (first.unwrap_err(), second.unwrap_err());
+        assert_eq!(*first, "down");
+        assert!(
+            Arc::ptr_eq(&first, &second),
+            "both callers get the one error"
+        );
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+                .await,
+            Ok(7)
+        );
+        assert_eq!(loads.load(Ordering::SeqCst), 2, "the error wasn't cached");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn the_next_call_after_a_loader_panics_loads_again() {
+        let cache = new_cache();
+        let caller = tokio::spawn({
+            let cache = cache.clone();
+            async move {
+                cache
+                    .get_or_try_insert_with(1, async {
+                        panic!("the loader broke") as Result<u64, ()>
+                    })
+                    .await
+            }
+        });
+        assert!(caller.await.unwrap_err().is_panic());
+
+        let loads = Arc::new(AtomicUsize::new(0));
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+                .await,
+            Ok(7)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_set_during_a_load_wins() {
+        let cache = new_cache();
+        let loads = Arc::new(AtomicUsize::new(0));
+        let (loaded, ()) = tokio::join!(
+            cache.get_or_try_insert_with(1, slow_loader(&loads, Ok(7))),
+            async {
+                sleep(Duration::from_millis(100)).await;
+                cache.set(1, 8).await;
+            },
+        );
+        assert_eq!(loaded, Ok(7), "the caller waiting gets the load's value");
+        assert_eq!(
+            cache.get(&1).await,
+            Some(8),
+            "the cache keeps the newer set"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn values_past_their_refresh_age_are_served_while_one_reload_runs() {
+        let cache = new_cache().with_refresh_after(Duration::from_secs(10));
+        let loads = Arc::new(AtomicUsize::new(0));
+        cache
+            .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+            .await
+            .unwrap();
+        sleep(Duration::from_secs(11)).await;
+
+        for _ in 0..5 {
+            assert_eq!(
+                cache
+                    .get_or_try_insert_with(1, slow_loader(&loads, Ok(8)))
+                    .await,
+                Ok(7),
+                "the old value is served right away"
+            );
+        }
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(cache.get(&1).await, Some(8), "the reload finished");
+        assert_eq!(loads.load(Ordering::SeqCst), 2, "one load, and one reload");
+        assert_eq!(cache.stats().refreshes, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expired_values_wait_for_a_reload() {
+        let cache = new_cache().with_expire_after(Duration::from_secs(10));
+        let loads = Arc::new(AtomicUsize::new(0));
+        cache
+            .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+            .await
+            .unwrap();
+        sleep(Duration::from_secs(11)).await;
+
+        assert_eq!(cache.get(&1).await, None);
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Ok(8)))
+                .await,
+            Ok(8)
+        );
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (0, 3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expired_values_are_served_stale_for_a_while_when_reloads_fail() {
+        let cache = new_cache()
+            .with_expire_after(Duration::from_secs(10))
+            .with_stale_for(Duration::from_secs(5));
+        let loads = Arc::new(AtomicUsize::new(0));
+        cache
+            .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+            .await
+            .unwrap();
+        sleep(Duration::from_secs(11)).await;
+
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Err("down")))
+                .await,
+            Ok(7),
+            "the reload failed within the stale period"
+        );
+        sleep(Duration::from_secs(5)).await;
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Err("down")))
+                .await
+                .map_err(|error| *error),
+            Err("down"),
+            "the stale period is over"
+        );
+        let stats = cache.stats();
+        assert_eq!((stats.stale_serves, stats.load_errors), (1, 2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn absent_results_and_errors_are_cached_for_their_own_while() {
+        let cache = new_cache()
+            .with_expire_after(Duration::from_secs(60))
+            .with_negative_expire_after(Duration::from_secs(5));
+        let loads = Arc::new(AtomicUsize::new(0));
+        for _ in 0..3 {
+            assert_eq!(
+                cache
+                    .get_or_try_insert_optional_with(1, slow_loader(&loads, Ok(None)))
+                    .await,
+                Ok(None)
+            );
+            assert_eq!(
+                cache
+                    .get_or_try_insert_with(2, slow_loader(&loads, Err("down")))
+                    .await
+                    .map_err(|error| *error),
+                Err("down")
+            );
+        }
+        assert_eq!(cache.get(&1).await, None);
+        assert_eq!(loads.load(Ordering::SeqCst), 2, "one load of each key");
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.absent_loads, stats.absent_hits, stats.error_hits),
+            (1, 3, 2)
+        );
+
+        sleep(Duration::from_secs(5)).await;
+        assert_eq!(
+            cache
+                .get_or_try_insert_optional_with(1, slow_loader(&loads, Ok(Some(7))))
+                .await,
+            Ok(Some(7)),
+            "the absent result expired long before a value would have"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_cached_absent_result_is_a_miss_for_loaders_that_always_find_a_value() {
+        let cache = new_cache().with_negative_expire_after(Duration::from_secs(5));
+        let loads = Arc::new(AtomicUsize::new(0));
+        cache
+            .get_or_try_insert_optional_with(1, slow_loader(&loads, Ok(None)))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+                .await,
+            Ok(7)
+        );
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_failed_refresh_keeps_the_value() {
+        let cache = new_cache()
+            .with_refresh_after(Duration::from_secs(10))
+            .with_negative_expire_after(Duration::from_secs(5));
+        let loads = Arc::new(AtomicUsize::new(0));
+        cache
+            .get_or_try_insert_with(1, slow_loader(&loads, Ok(7)))
+            .await
+            .unwrap();
+        sleep(Duration::from_secs(11)).await;
+
+        assert_eq!(
+            cache
+                .get_or_try_insert_with(1, slow_loader(&loads, Err("down")))
+                .await,
+            Ok(7)
+        );
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(cache.stats().load_errors, 1, "the refresh failed");
+        assert_eq!(cache.get(&1).await, Some(7), "and didn't replace the value");
+    }
+
+    /// Runs `PROCESSES` caches, as though each were in a process of its own, that all load a key
+    /// at once and then read it every 50 to 150 ms, with loads that take a second. Returns the
+    /// most reloads that ran at once, after the first loads.
+    async fn most_simultaneous_reloads(early_expiration: Option<f64>) -> usize {
+        const PROCESSES: u64 = 50;
+        let start = tokio::time::Instant::now();
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let most_in_flight = Arc::new(AtomicUsize::new(0));
+        let reload = {
+            let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+            move || {
+                let (in_flight, most_in_flight) = (in_flight.clone(), most_in_flight.clone());
+                async move {
+                    let reloading = Duration::from_secs(5) < start.elapsed();
+                    if reloading {
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        most_in_flight.fetch_max(now, Ordering::SeqCst);
+                    }
+                    sleep(Duration::from_secs(1)).await;
+                    if reloading {
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                    }
+                    Ok::<_, ()>(7)
+                }
+            }
+        };
+
+        let processes: Vec<_> = (0..PROCESSES)
+            .map(|process| {
+                let mut cache = new_cache()
+                    .with_expire_after(Duration::from_secs(60))
+                    .with_random_seed(process);
+                if let Some(beta) = early_expiration {
+                    cache = cache.with_early_expiration(beta);
+                }
+                let reload = reload.clone();
+                tokio::spawn(async move {
+                    let mut random = StdRng::seed_from_u64(process);
+                    while start.elapsed() < Duration::from_secs(90) {
+                        cache.get_or_try_insert_with(1, reload()).await.unwrap();
+                        sleep(Duration::from_millis(random.gen_range(50..150))).await;
+                    }
+                })
+            })
+            .collect();
+        for process in processes {
+            process.await.unwrap();
+        }
+        most_in_flight.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn early_expiration_spreads_out_reloads_across_processes() {
+        let without = most_simultaneous_reloads(None).await;
+        let with = most_simultaneous_reloads(Some(1.0)).await;
+        assert_eq!(
+            without, 50,
+            "every process reloads when the value expires, not {without}"
+        );
+        assert!(
+            with <= without / 2,
+            "{with} reloads at once with early expiration, and {without} without"
+        );
+    }
+}
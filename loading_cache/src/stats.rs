@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What a [LoadingCache](crate::LoadingCache) has done since it was made.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadingStats {
+    /// Reads that found a value to serve right away.
+    pub hits: u64,
+    /// Reads that found no value, or an expired one.
+    pub misses: u64,
+    /// Loads that returned an error.
+    pub load_errors: u64,
+    /// Background reloads of values past their refresh age, started by the reads that found them.
+    pub refreshes: u64,
+    /// Background reloads of values about to expire, started by the reads that drew them by
+    /// early expiration.
+    pub early_refreshes: u64,
+    /// Reads that got an expired value, because reloading it failed.
+    pub stale_serves: u64,
+    /// Loads that found nothing for their key.
+    pub absent_loads: u64,
+    /// Reads that found a cached absent result, and got nothing without loading.
+    pub absent_hits: u64,
+    /// Reads that found a cached loader error, and got it without loading.
+    pub error_hits: u64,
+}
+
+/// The counters behind [LoadingStats]. They're shared with load tasks, which count their errors
+/// and absent results.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    pub hits: AtomicU64,
+    pub misses: AtomicU64,
+    pub load_errors: AtomicU64,
+    pub refreshes: AtomicU64,
+    pub early_refreshes: AtomicU64,
+    pub stale_serves: AtomicU64,
+    pub absent_loads: AtomicU64,
+    pub absent_hits: AtomicU64,
+    pub error_hits: AtomicU64,
+}
+
+impl Counters {
+    pub fn count(counter: &AtomicU64) {
+        // Each counter is only ever read on its own, so no ordering with other memory is needed.
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> LoadingStats {
+        LoadingStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            load_errors: self.load_errors.load(Ordering::Relaxed),
+            refreshes: self.refreshes.load(Ordering::Relaxed),
+            early_refreshes: self.early_refreshes.load(Ordering::Relaxed),
+            stale_serves: self.stale_serves.load(Ordering::Relaxed),
+            absent_loads: self.absent_loads.load(Ordering::Relaxed),
+            absent_hits: self.absent_hits.load(Ordering::Relaxed),
+            error_hits: self.error_hits.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -9,24 +9,96 @@ pub struct SieveCache<Key, Value> {
 
 impl<Key, Value> SieveCache<Key, Value> {
     pub fn new() -> Self {
+        Self::with_capacity(cache::MAX_SIZE)
+    }
+
+    /// The simulator in the benchmarking crate runs caches at many capacities. You'll want to
+    /// keep track of the capacity once you have somewhere to put it.
+    pub fn with_capacity(_capacity: usize) -> Self {
         Self {
             _phantom: std::marker::PhantomData,
         }
     }
 }
 
+impl<Key, Value> Default for SieveCache<Key, Value> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Key, Value> SizeLimitedCache<Key, Value> for SieveCache<Key, Value>
 // See the comment on SieveCache for commentary on "where clauses" in rust.
 where
     Key: Clone + Eq + std::hash::Hash,
     Value: Clone,
 {
-    fn get(&mut self, key: &Key) -> Option<Value> {
+    fn get(&mut self, _key: &Key) -> Option<Value> {
         // todo!()
         None
     }
 
-    fn set(&mut self, key: Key, value: Value) {
+    fn set(&mut self, _key: Key, _value: Value) {
         // todo!()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use cache::synchronized_cache;
+    use cache_testkit::{
+        differential::{self, Side},
+        sieve_model::ResidentKeys,
+        Expectations,
+    };
+
+    use crate::SieveCache;
+
+    // These check that your cache behaves like a cache. Once it stores things, run them with
+    // `cargo test -p sieve_cache -- --ignored`, and then delete the `#[ignore]`s.
+    cache_testkit::size_limited_cache_tests!(
+        SieveCache::with_capacity,
+        Expectations::STRICT,
+        #[ignore = "the workshop cache is yours to write!"]
+    );
+    cache_testkit::shareable_cache_tests!(
+        |capacity| synchronized_cache(SieveCache::with_capacity(capacity)),
+        Expectations::STRICT,
+        #[ignore = "the workshop cache is yours to write!"]
+    );
+
+    // These run random gets and sets against your cache and a reference SIEVE side by side, and
+    // check that your cache evicts exactly the key SIEVE would, every time. A failure prints the
+    // shortest sequence proptest could find that still fails.
+    cache_testkit::sieve_model_tests!(
+        SieveCache::with_capacity,
+        #[ignore = "the workshop cache is yours to write!"]
+    );
+
+    // This runs your cache and the example cache on the same seeded streams of gets and sets,
+    // and stops at the first step where they return different values or hold different keys. It
+    // prints the steps leading up to it, with the seed that makes that stream.
+    #[test]
+    #[ignore = "the workshop cache is yours to write!"]
+    fn matches_the_example_cache() {
+        differential::assert_same(
+            Side {
+                name: "workshop",
+                new: SieveCache::with_capacity,
+            },
+            Side {
+                name: "example",
+                new: example_sieve_cache::SieveCache::with_capacity,
+            },
+            0..500,
+        );
+    }
+
+    impl ResidentKeys<u64> for SieveCache<u64, u64> {
+        /// List the keys your cache holds, without marking any of them as read.
+        fn resident_keys(&self) -> Vec<u64> {
+            // todo!()
+            Vec::new()
+        }
+    }
+}